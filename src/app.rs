@@ -1,38 +1,114 @@
-use crosses_core::{
-    board_manager::{Cell, CellKind},
-    player_manager::PlayerManager,
-};
+use crosses_core::board_manager::{BoardManager, Cell, CellKind};
+use crosses_core::player_manager::GameState;
 use egui::Color32;
 
-use crate::sample_core::{sample_cell::SampleCell, CrossesCore};
+mod board_widget;
+mod multiplayer;
+mod new_game;
+mod persistence;
+mod replay;
+
+use crate::sample_core::{
+    bot::{Bot, Difficulty},
+    evaluation::Evaluation,
+    move_notation::format_index,
+    preview::MovePreview,
+    sample_board_manager,
+    validation::Discrepancy,
+    CrossesCore, GameSettings, LoseReason,
+};
+
+/// Pause between bot moves, in seconds, so that its turn can be followed by eye.
+const BOT_DELAY: f64 = 0.4;
+/// How often a running clock is redrawn.
+const CLOCK_REFRESH: std::time::Duration = std::time::Duration::from_millis(100);
+
+const UNDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+const REDO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y);
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TemplateApp {
+    version: u32,
     game: CrossesCore,
     current_error: String,
     export_field: String,
     import_field: String,
-    max_x: usize,
-    max_y: usize,
+    new_game: GameSettings,
+    new_game_text: new_game::LayoutText,
+    new_game_open: bool,
     focused: Option<(usize, usize)>,
+    bots: [Option<Difficulty>; sample_board_manager::MAX_PLAYERS],
+    heatmap: bool,
+    board_style: board_widget::BoardStyle,
+    network: multiplayer::NetworkSettings,
+    network_open: bool,
+    #[serde(skip)]
+    bot: Bot,
+    #[serde(skip)]
+    bot_wait_until: f64,
+    /// `current_move` at which a guest's bot sent its move to the host. Until
+    /// the host echoes it back the position doesn't change and the bot waits.
+    #[serde(skip)]
+    bot_sent: Option<usize>,
+    /// `egui` time of the last clock tick. Time passes only while the app is
+    /// open, so a saved timed game resumes with the time it had.
+    #[serde(skip)]
+    clock_time: Option<f64>,
+    #[serde(skip)]
+    hovered: Option<(usize, usize)>,
+    #[serde(skip)]
+    preview: Option<MovePreview>,
+    /// Only computed while the heatmap is shown.
+    #[serde(skip)]
+    evaluation: Option<Evaluation>,
+    /// `current_move` of the ended game whose game-over window was closed.
+    #[serde(skip)]
+    game_over_dismissed: Option<usize>,
+    #[serde(skip)]
+    replay: Option<replay::Replay>,
+    /// Result of the last "validate board" run, shown until closed.
+    #[serde(skip)]
+    validation: Option<Vec<Discrepancy>>,
+    #[serde(skip)]
+    session: Option<multiplayer::Session>,
+    /// Connections, rejected moves and errors of the current session.
+    #[serde(skip)]
+    network_log: Vec<String>,
 }
 
 impl Default for TemplateApp {
     fn default() -> Self {
         Self {
-            game: CrossesCore {
-                board_manager: Default::default(),
-                player_manager: PlayerManager::new(4, 2, [None; 2]),
-                log: Default::default(),
-            },
+            version: persistence::STATE_VERSION,
+            game: Default::default(),
             current_error: Default::default(),
             export_field: Default::default(),
             import_field: Default::default(),
-            max_x: 10,
-            max_y: 10,
+            new_game: Default::default(),
+            new_game_text: Default::default(),
+            new_game_open: false,
             focused: Default::default(),
+            bots: Default::default(),
+            heatmap: false,
+            board_style: Default::default(),
+            network: Default::default(),
+            network_open: false,
+            bot: Default::default(),
+            bot_wait_until: 0.0,
+            bot_sent: None,
+            clock_time: None,
+            hovered: None,
+            preview: None,
+            evaluation: None,
+            game_over_dismissed: None,
+            replay: None,
+            validation: None,
+            session: None,
+            network_log: Vec::new(),
         }
     }
 }
@@ -45,9 +121,9 @@ impl TemplateApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        // if let Some(storage) = cc.storage {
-        //     return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        // }
+        if let Some(storage) = cc.storage {
+            return persistence::restore(storage);
+        }
 
         Default::default()
     }
@@ -61,6 +137,16 @@ impl eframe::App for TemplateApp {
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.network_step();
+        if !self.game_over_shown() {
+            if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) && self.can_back() {
+                self.undo();
+            }
+            if ctx.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) && self.can_forward() {
+                self.redo();
+            }
+        }
+
         // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
         // For inspiration and more examples, go to https://emilk.github.io/egui
 
@@ -68,99 +154,528 @@ impl eframe::App for TemplateApp {
             // The top panel is often a good place for a menu bar:
 
             egui::menu::bar(ui, |ui| {
+                if ui
+                    .add_enabled(!self.is_guest(), egui::Button::new("Новая игра"))
+                    .clicked()
+                {
+                    self.open_new_game();
+                }
+                if ui.button("Сеть").clicked() {
+                    self.network_open = true;
+                }
+                ui.separator();
+                if ui
+                    .add_enabled(self.can_back(), egui::Button::new("⟲ Отменить"))
+                    .on_hover_text("Ctrl+Z")
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui
+                    .add_enabled(self.can_forward(), egui::Button::new("⟳ Вернуть"))
+                    .on_hover_text("Ctrl+Y")
+                    .clicked()
+                {
+                    self.redo();
+                }
+                ui.separator();
+                if ui
+                    .add_enabled(
+                        self.replay.is_some() || self.can_review(),
+                        egui::SelectableLabel::new(self.replay.is_some(), "Разбор"),
+                    )
+                    .on_hover_text("Пройтись по ходам партии")
+                    .on_disabled_hover_text("Партию с часами разбирают, когда она закончится")
+                    .clicked()
+                {
+                    if self.replay.is_some() {
+                        self.close_replay();
+                    } else {
+                        self.open_replay(false);
+                    }
+                }
+                ui.checkbox(&mut self.heatmap, "Тепловая карта")
+                    .on_hover_text("Подсветить, кто сильнее дотягивается до каждой клетки");
+                ui.menu_button("Вид", |ui| {
+                    let style = &mut self.board_style;
+                    ui.checkbox(&mut style.hatch_dead, "Штриховать мёртвые клетки");
+                    ui.checkbox(&mut style.outline_important, "Обводить важные клетки");
+                    ui.checkbox(&mut style.connections, "Связи внутри групп")
+                        .on_hover_text("Как закрашенные клетки группы находят друг друга");
+                });
+                if ui
+                    .button("Проверить доску")
+                    .on_hover_text("Пересчитать состояние доски с нуля и сравнить с текущим")
+                    .clicked()
+                {
+                    self.validation = Some(self.game.board_manager.validate());
+                }
+                ui.separator();
                 egui::widgets::global_dark_light_mode_buttons(ui);
             });
         });
 
+        self.new_game_window(ctx);
+        self.network_window(ctx);
+        self.clock_step(ctx);
+        self.bot_step(ctx);
+        self.replay_step(ctx);
+        self.preview = match (self.hovered, self.current_bot(), &self.replay) {
+            (Some((x, y)), None, None) => self.game.simulate_move(x, y).ok(),
+            _ => None,
+        };
+        self.evaluation = self.heatmap.then(|| self.game.board_manager.evaluate());
+
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| self.status_bar(ui));
+        if self.replay.is_some() {
+            egui::TopBottomPanel::bottom("replay").show(ctx, |ui| self.replay_panel(ui));
+        }
+        self.game_over_window(ctx);
+        self.validation_window(ctx);
+        egui::SidePanel::right("info").show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| self.info(ui));
+        });
+
         egui::CentralPanel::default().show(ctx, |ui| {
             // The central panel the region left after adding TopPanel's and SidePanel's
             ui.heading("Крестики (Версия для ценителей)");
 
-            ui.horizontal(|ui| {
-                ui.label("Загрузить игру: ");
-                ui.text_edit_singleline(&mut self.import_field);
-            });
-            ui.label("Выгрузить игру: WIP");
-            ui.horizontal(|ui| {
-                self.game_board(ui);
-                self.info(ui);
-            });
-
-            ui.separator();
-
-            ui.add(egui::github_link_file!(
-                "https://github.com/emilk/eframe_template/blob/main/",
-                "Source code."
-            ));
+            self.import_export(ui);
+            self.players(ui);
 
+            // The footer is laid out first, from the bottom, so that the board
+            // can take all the space that is left above it.
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                 powered_by_egui_and_eframe(ui);
                 egui::warn_if_debug_build(ui);
+                ui.add(egui::github_link_file!(
+                    "https://github.com/emilk/eframe_template/blob/main/",
+                    "Source code."
+                ));
+                ui.separator();
+                ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
+                    self.game_board(ui);
+                });
             });
         });
+
+        // Whatever changed during this frame goes out without waiting for the next one.
+        self.network_step();
     }
 }
 
 impl TemplateApp {
-    fn game_board(&mut self, ui: &mut egui::Ui) {
-        ui.vertical(|ui| {
-            for y in 0..self.game.board_manager.max_y {
+    fn import_export(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Загрузить игру", |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut self.import_field)
+                    .code_editor()
+                    .desired_rows(4),
+            );
+            ui.horizontal(|ui| {
+                if ui.button("Загрузить").clicked() {
+                    match CrossesCore::import(&self.import_field) {
+                        Ok(game) => self.load_game(game),
+                        Err(e) => self.current_error = e.to_string(),
+                    }
+                }
+                if ui.button("Проиграть ходы").clicked() {
+                    // The notation has no times, so the replayed game isn't timed.
+                    let settings = GameSettings {
+                        time_control: None,
+                        ..self.game.settings.clone()
+                    };
+                    let mut game =
+                        CrossesCore::new(settings).expect("settings of the current game are valid");
+                    match game.replay(&self.import_field) {
+                        Ok(()) => self.load_game(game),
+                        Err(e) => self.current_error = e.to_string(),
+                    }
+                }
+            });
+        });
+        ui.collapsing("Выгрузить игру", |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Выгрузить").clicked() {
+                    self.export_field = self.game.export();
+                }
+                if ui.button("Записать ходы").clicked() {
+                    match self.game.notation() {
+                        Ok(notation) => self.export_field = notation,
+                        Err(e) => self.current_error = e.to_string(),
+                    }
+                }
+                if ui.button("Копировать").clicked() {
+                    ui.ctx().copy_text(self.export_field.clone());
+                }
+            });
+            ui.add(
+                egui::TextEdit::multiline(&mut self.export_field)
+                    .code_editor()
+                    .desired_rows(4)
+                    .interactive(false),
+            );
+        });
+    }
+    fn status_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if let GameState::Ended(game_over) = self.game.player_manager.game_state() {
+                ui.label(format!("Игра окончена: {game_over}"));
+                return;
+            }
+            let player = self.game.player_manager.current_player();
+            ui.label("Ход");
+            ui.label(
+                egui::RichText::new(format!("● {}", player_name(player)))
+                    .strong()
+                    .color(player_color(player)),
+            );
+            ui.separator();
+            ui.label(format!(
+                "Осталось ходов: {} из {}",
+                self.game.settings.moves_per_turn - self.game.moves_made_in_turn(),
+                self.game.settings.moves_per_turn
+            ));
+            ui.separator();
+            ui.label(format!(
+                "Сделано ходов: {}",
+                self.game.player_manager.current_move()
+            ));
+            if let Some(difficulty) = self.current_bot() {
+                ui.separator();
+                ui.label(difficulty_name(difficulty));
+            } else if self.is_spectator() {
+                ui.separator();
+                ui.label("Только смотрим");
+            } else if !self.is_local_player(self.game.player_manager.current_player()) {
+                ui.separator();
+                ui.label("Ходит соперник по сети");
+            }
+        });
+    }
+    /// Whether the game-over dialog is up. It's modal: the rest of the app is
+    /// covered and its shortcuts are off until the dialog is closed.
+    fn game_over_shown(&self) -> bool {
+        self.game.is_over()
+            && self.game_over_dismissed != Some(self.game.player_manager.current_move())
+    }
+    fn game_over_window(&mut self, ctx: &egui::Context) {
+        let GameState::Ended(game_over) = self.game.player_manager.game_state() else {
+            return;
+        };
+        let current_move = self.game.player_manager.current_move();
+        if self.game_over_dismissed == Some(current_move) {
+            return;
+        }
+        egui::Area::new(egui::Id::new("game_over_backdrop"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(egui::Pos2::ZERO)
+            .show(ctx, |ui| {
+                let screen = ui.ctx().screen_rect();
+                ui.painter()
+                    .rect_filled(screen, 0.0, egui::Color32::from_black_alpha(96));
+                // Takes every click and drag that would reach the app behind.
+                ui.allocate_rect(screen, egui::Sense::click_and_drag());
+            });
+        let window = egui::Window::new("Игра окончена")
+            .order(egui::Order::Foreground)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.heading(game_over.to_string());
+                for player in 0..self.game.settings.players() {
+                    let reason = match self.game.lose_reason(player) {
+                        Some(LoseReason::OutOfMoves) => "не осталось клеток, куда можно сходить",
+                        Some(LoseReason::OutOfCrosses) => "не осталось ни одного крестика",
+                        Some(LoseReason::OutOfTime) => "кончилось время",
+                        None => continue,
+                    };
+                    ui.label(
+                        egui::RichText::new(format!("У {} {reason}", player_name(player)))
+                            .color(player_color(player)),
+                    );
+                }
+                ui.separator();
                 ui.horizontal(|ui| {
-                    for x in 0..self.game.board_manager.max_x {
-                        let cell = self.game.board_manager.board[x][y];
-                        let mut button = egui::Button::new(if cell.kind() == CellKind::Cross {
-                            egui::RichText::new("x").monospace().color(get_color(cell))
-                        } else {
-                            egui::RichText::new(" ").monospace()
-                        });
-                        if cell.kind() == CellKind::Filled {
-                            button = button.fill(get_color(cell));
-                        } else {
-                            if cell.is_checked() {
-                                button = button.fill(Color32::GOLD)
-                            } else if cell.is_active(self.game.player_manager.current_player() == 1)
-                            {
-                                button = button.fill(Color32::GRAY)
-                            }
-                        }
-                        let response = ui.add(button);
-                        if response.clicked() {
-                            self.game.board_manager.clear_checked();
-                            if let Err(e) = self.game.make_move(x, y) {
-                                self.current_error = e.to_string();
-                            }
-                        }
-                        if response.secondary_clicked() {
-                            self.focused = Some((x, y));
+                    if ui.button("Реванш").clicked() {
+                        match CrossesCore::new(self.game.settings.clone()) {
+                            Ok(game) => self.load_game(game),
+                            Err(e) => self.current_error = e.to_string(),
                         }
                     }
+                    if ui
+                        .button("Разбор")
+                        .on_hover_text("Просмотреть партию с первого хода")
+                        .clicked()
+                    {
+                        self.game_over_dismissed = Some(current_move);
+                        self.open_replay(true);
+                    }
+                    if ui.button("Выгрузить").clicked() {
+                        self.export_field = self.game.export();
+                        ui.ctx().copy_text(self.export_field.clone());
+                        self.game_over_dismissed = Some(current_move);
+                    }
+                    if ui.button("Закрыть").clicked() {
+                        self.game_over_dismissed = Some(current_move);
+                    }
+                });
+            });
+        // Both layers are in the foreground, the dialog has to stay above its backdrop.
+        if let Some(window) = window {
+            ctx.move_to_top(window.response.layer_id);
+        }
+    }
+    fn validation_window(&mut self, ctx: &egui::Context) {
+        let Some(discrepancies) = &self.validation else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Проверка доски")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                if discrepancies.is_empty() {
+                    ui.label("Доска в порядке");
+                    return;
+                }
+                ui.label(format!("Расхождений: {}", discrepancies.len()));
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for discrepancy in discrepancies {
+                        ui.monospace(discrepancy.to_string());
+                    }
+                });
+            });
+        if !open {
+            self.validation = None;
+        }
+    }
+    fn players(&mut self, ui: &mut egui::Ui) {
+        ui.collapsing("Игроки", |ui| {
+            for player in 0..self.game.settings.players() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("За {}:", player_name(player)));
+                    let bot = &mut self.bots[player];
+                    ui.radio_value(bot, None, "Человек");
+                    for difficulty in Difficulty::ALL {
+                        ui.radio_value(bot, Some(difficulty), difficulty_name(difficulty));
+                    }
                 });
             }
         });
     }
+    fn current_bot(&self) -> Option<Difficulty> {
+        let player = self.game.player_manager.current_player();
+        if !self.is_local_player(player) {
+            return None;
+        }
+        self.bots.get(player).copied().flatten()
+    }
+    /// Runs the current player's clock, see `CrossesCore::tick`.
+    fn clock_step(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        let last = self.clock_time.replace(now);
+        if self.game.clock.is_none() || self.game.is_over() || self.game.can_forward() {
+            return;
+        }
+        if let Some(last) = last {
+            let elapsed = std::time::Duration::from_secs_f64((now - last).max(0.0));
+            // A guest counts down between the clocks the host sends with every
+            // move, but only the host knocks out a player whose time ran out.
+            if self.is_guest() {
+                self.game.spend_time(elapsed);
+            } else {
+                self.game.tick(elapsed);
+            }
+        }
+        ctx.request_repaint_after(CLOCK_REFRESH);
+    }
+    /// Makes one bot move if it's a bot's turn. Bots don't play while
+    /// a game is being reviewed, only at the end of the log.
+    fn bot_step(&mut self, ctx: &egui::Context) {
+        let Some(difficulty) = self.current_bot() else {
+            return;
+        };
+        if self.game.is_over() || self.game.can_forward() || self.replay.is_some() {
+            return;
+        }
+        let current_move = self.game.player_manager.current_move();
+        if self.bot_sent == Some(current_move) {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        if now < self.bot_wait_until {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(
+                self.bot_wait_until - now,
+            ));
+            return;
+        }
+        let Some((x, y)) = self.bot.choose_move(&self.game, difficulty) else {
+            return;
+        };
+        if self.is_guest() {
+            self.bot_sent = Some(current_move);
+        }
+        self.play(x, y);
+        self.bot_wait_until = now + BOT_DELAY;
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(BOT_DELAY));
+    }
+    /// Guests follow the host's game and can't step through it themselves.
+    /// Timed games can't be taken back at all, their clock only knows the present.
+    fn can_back(&self) -> bool {
+        !self.is_guest() && self.game.clock.is_none() && self.game.can_back()
+    }
+    fn can_forward(&self) -> bool {
+        !self.is_guest() && self.game.clock.is_none() && self.game.can_forward()
+    }
+    /// A timed game is looked back over only once it's over: until then its
+    /// clock runs for the position at the end of the log.
+    fn can_review(&self) -> bool {
+        self.game.clock.is_none() || self.game.is_finished()
+    }
+    fn undo(&mut self) {
+        self.game.board_manager.clear_checked();
+        if let Err(e) = self.game.back() {
+            self.current_error = e.to_string();
+        }
+    }
+    fn redo(&mut self) {
+        self.game.board_manager.clear_checked();
+        if let Err(e) = self.game.forward() {
+            self.current_error = e.to_string();
+        }
+    }
+    fn load_game(&mut self, game: CrossesCore) {
+        if self.is_guest() {
+            self.current_error = "Партию выбирает тот, кто создал игру".to_string();
+            return;
+        }
+        self.game = game;
+        self.focused = None;
+        self.replay = None;
+        self.current_error.clear();
+    }
+    fn game_board(&mut self, ui: &mut egui::Ui) {
+        let board = board_widget::BoardWidget::new(
+            &self.game.board_manager,
+            self.game.player_manager.current_player(),
+        )
+        .heat(
+            self.evaluation
+                .as_ref()
+                .map(|evaluation| &evaluation.heat[..]),
+        )
+        .preview(self.preview.as_ref())
+        .highlighted(self.replay.as_ref().and(self.game.last_move()))
+        .style(self.board_style)
+        .show(ui);
+        self.hovered = board.hovered;
+        let Some((x, y)) = board.hovered else {
+            return;
+        };
+        let player = self.game.player_manager.current_player();
+        if board.response.clicked()
+            && self.current_bot().is_none()
+            && self.is_local_player(player)
+            && self.replay.is_none()
+        {
+            self.play(x, y);
+        }
+        if board.response.secondary_clicked() {
+            self.focused = Some((x, y));
+        }
+    }
+    /// Rows of the info grid for switching between variations of the game tree.
+    fn variations(&mut self, ui: &mut egui::Ui) {
+        let variations = self.game.variations();
+        let next = self
+            .game
+            .log
+            .get(self.game.player_manager.current_move())
+            .copied();
+        ui.label("Продолжения:");
+        ui.horizontal_wrapped(|ui| {
+            if variations.is_empty() {
+                ui.label("нет");
+            }
+            for (number, index) in variations.into_iter().enumerate() {
+                let mut text = egui::RichText::new(format_index(index)).monospace();
+                if number == 0 {
+                    text = text.strong();
+                }
+                if ui.selectable_label(next == Some(index), text).clicked() {
+                    if let Err(e) = self.game.choose_variation(index) {
+                        self.current_error = e.to_string();
+                    }
+                }
+            }
+        });
+        ui.end_row();
+        ui.label("Линия:");
+        if self.game.is_main_line() {
+            ui.label("главная");
+        } else if ui.button("Сделать главной").clicked() {
+            self.game.promote_variation();
+        }
+        ui.end_row();
+    }
     fn info(&mut self, ui: &mut egui::Ui) {
         ui.vertical(|ui| {
             egui::Grid::new("Info").show(ui, |ui| {
                 ui.label("Ошибка:");
                 ui.label(self.current_error.to_string());
                 ui.end_row();
-                ui.label("Ходы и крестики синих:");
-                ui.monospace(format!(
-                    "({}, {})",
-                    self.game.board_manager.moves_counter[0],
-                    self.game.board_manager.crosses_counter[0]
-                ));
-                ui.end_row();
-                ui.label("Ходы и крестики красных:");
-                ui.monospace(format!(
-                    "({}, {})",
-                    self.game.board_manager.moves_counter[1],
-                    self.game.board_manager.crosses_counter[1]
-                ));
+                for player in 0..self.game.settings.players() {
+                    ui.label(format!("Ходы и крестики {}:", player_name(player)));
+                    let board = &self.game.board_manager;
+                    let mut counters = format!(
+                        "({}, {})",
+                        board.moves_counter[player], board.crosses_counter[player]
+                    );
+                    if let Some(preview) = &self.preview {
+                        counters += &format!(
+                            " → ({}, {})",
+                            preview.moves_counter[player], preview.crosses_counter[player]
+                        );
+                    }
+                    ui.monospace(counters);
+                    ui.end_row();
+                    if let Some(clock) = &self.game.clock {
+                        ui.label(format!("Часы {}:", player_name(player)));
+                        let mut text =
+                            egui::RichText::new(format_clock(clock.remaining[player])).monospace();
+                        if player == self.game.player_manager.current_player()
+                            && !self.game.is_over()
+                        {
+                            text = text.strong();
+                        }
+                        ui.label(text);
+                        ui.end_row();
+                    }
+                }
+                if let Some(evaluation) = &self.evaluation {
+                    for (player, score) in evaluation.scores.iter().enumerate() {
+                        ui.label(format!("Оценка {}:", player_name(player)));
+                        ui.monospace(score.total().to_string())
+                            .on_hover_text(format!(
+                                "Подвижность: {}\nТерритория: {}\nВажные крестики: {}\n\
+                             Живые группы: {}\nМёртвые группы: {}",
+                                score.mobility,
+                                score.territory,
+                                score.important_crosses,
+                                score.alive_groups,
+                                score.dead_groups
+                            ));
+                        ui.end_row();
+                    }
+                }
+                ui.label("Хеш позиции:");
+                ui.monospace(format!("{:016x}", self.game.board_manager.hash));
                 ui.end_row();
+                self.variations(ui);
                 let focused_cell = self
                     .focused
-                    .map(|(x, y)| self.game.board_manager.board[x][y]);
+                    .map(|(x, y)| self.game.board_manager.get((x, y)));
                 ui.label("Координаты:");
                 if let Some((x, y)) = self.focused {
                     ui.monospace(format!("x: {x} y: {y}"));
@@ -179,27 +694,18 @@ impl TemplateApp {
                 ui.label("Игрок:");
                 if let Some(cell) = focused_cell {
                     ui.monospace(match cell.kind() {
-                        CellKind::Cross | CellKind::Filled => {
-                            if cell.player() {
-                                "Красный"
-                            } else {
-                                "Синий"
-                            }
-                        }
+                        CellKind::Cross | CellKind::Filled => PLAYERS[cell.player()].color_name,
                         _ => "Никакого",
                     });
                 }
                 ui.end_row();
-                ui.label("Красные активации:");
-                if let Some(cell) = focused_cell {
-                    ui.monospace(format!("{}", cell.activity(true)));
-                }
-                ui.end_row();
-                ui.label("Синие активации:");
-                if let Some(cell) = focused_cell {
-                    ui.monospace(format!("{}", cell.activity(false)));
+                for player in 0..self.game.settings.players() {
+                    ui.label(format!("Активации {}:", player_name(player)));
+                    if let Some(cell) = focused_cell {
+                        ui.monospace(format!("{}", cell.activity(player)));
+                    }
+                    ui.end_row();
                 }
-                ui.end_row();
                 ui.label("Перегрев:");
                 if let Some(cell) = focused_cell {
                     ui.monospace(if cell.is_overheated() {
@@ -240,13 +746,58 @@ impl TemplateApp {
         });
     }
 }
-fn get_color(cell: SampleCell) -> Color32 {
-    if cell.player() {
-        egui::Color32::RED
+/// How a player is shown, by player.
+struct PlayerLook {
+    /// Names the player's side, as in "ход синих".
+    name: &'static str,
+    /// Names the colour of a cell.
+    color_name: &'static str,
+    color: Color32,
+}
+const PLAYERS: [PlayerLook; sample_board_manager::MAX_PLAYERS] = [
+    PlayerLook {
+        name: "синих",
+        color_name: "Синий",
+        color: Color32::BLUE,
+    },
+    PlayerLook {
+        name: "красных",
+        color_name: "Красный",
+        color: Color32::RED,
+    },
+    PlayerLook {
+        name: "зелёных",
+        color_name: "Зелёный",
+        color: Color32::DARK_GREEN,
+    },
+    PlayerLook {
+        name: "жёлтых",
+        color_name: "Жёлтый",
+        color: Color32::from_rgb(0xd0, 0xa0, 0x00),
+    },
+];
+fn player_name(player: usize) -> &'static str {
+    PLAYERS[player].name
+}
+/// `m:ss`, with tenths of a second once little time is left.
+fn format_clock(remaining: std::time::Duration) -> String {
+    let seconds = remaining.as_secs();
+    if seconds < 10 {
+        format!("0:{:04.1}", remaining.as_secs_f64())
     } else {
-        egui::Color32::BLUE
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+fn difficulty_name(difficulty: Difficulty) -> &'static str {
+    match difficulty {
+        Difficulty::Easy => "Лёгкий бот",
+        Difficulty::Medium => "Средний бот",
+        Difficulty::Hard => "Сложный бот",
     }
 }
+fn player_color(player: usize) -> Color32 {
+    PLAYERS[player].color
+}
 fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
         ui.spacing_mut().item_spacing.x = 0.0;
@@ -0,0 +1,302 @@
+//! The board, painted cell by cell into one allocated rect.
+//!
+//! Cells are square and zoom to fit the space the widget is given. Pointer
+//! positions are mapped back to `(x, y)` by `BoardResponse::hovered`.
+//! What else is drawn over the cells is chosen by `BoardStyle`.
+use crosses_core::board_manager::{BoardManager, Cell, CellKind};
+use egui::{Color32, Rect, Sense, Shape, Stroke, Vec2};
+
+use super::player_color;
+use crate::sample_core::{
+    evaluation::Heat,
+    preview::MovePreview,
+    sample_board_manager::{MoveKind, SampleBoardManager},
+    sample_cell::SampleCell,
+};
+
+/// Side of a cell in points, whatever the space available.
+const MIN_CELL: f32 = 12.0;
+const MAX_CELL: f32 = 64.0;
+/// Space between neighbouring cells, as a share of the cell side.
+const GAP: f32 = 0.06;
+/// Distance between hatching lines, as a share of the cell side.
+const HATCH_STEP: f32 = 0.25;
+
+/// Optional layers of the board, persisted with the app.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct BoardStyle {
+    /// Hatch the filled cells of dead groups.
+    pub hatch_dead: bool,
+    /// Outline important crosses and filled cells instead of marking them with a dot.
+    pub outline_important: bool,
+    /// Link the filled cells of each group the way `traverse` walks them.
+    pub connections: bool,
+}
+impl Default for BoardStyle {
+    fn default() -> Self {
+        Self {
+            hatch_dead: true,
+            outline_important: true,
+            connections: false,
+        }
+    }
+}
+
+pub struct BoardWidget<'a> {
+    board: &'a SampleBoardManager,
+    player: usize,
+    heat: Option<&'a [Option<Heat>]>,
+    preview: Option<&'a MovePreview>,
+    highlighted: Option<(usize, usize)>,
+    style: BoardStyle,
+}
+
+pub struct BoardResponse {
+    pub response: egui::Response,
+    /// The cell under the pointer.
+    pub hovered: Option<(usize, usize)>,
+}
+
+impl<'a> BoardWidget<'a> {
+    /// Cells `player` can reach are shaded.
+    pub fn new(board: &'a SampleBoardManager, player: usize) -> Self {
+        Self {
+            board,
+            player,
+            heat: None,
+            preview: None,
+            highlighted: None,
+            style: Default::default(),
+        }
+    }
+    pub fn style(mut self, style: BoardStyle) -> Self {
+        self.style = style;
+        self
+    }
+    /// Shades cells in the colour of whoever reaches them best.
+    pub fn heat(mut self, heat: Option<&'a [Option<Heat>]>) -> Self {
+        self.heat = heat;
+        self
+    }
+    pub fn preview(mut self, preview: Option<&'a MovePreview>) -> Self {
+        self.preview = preview;
+        self
+    }
+    /// Outlines one cell, e.g. the last move.
+    pub fn highlighted(mut self, index: Option<(usize, usize)>) -> Self {
+        self.highlighted = index;
+        self
+    }
+    pub fn show(self, ui: &mut egui::Ui) -> BoardResponse {
+        let (max_x, max_y) = (self.board.max_x, self.board.max_y);
+        let available = ui.available_size();
+        let side = (available.x / max_x as f32)
+            .min(available.y / max_y as f32)
+            .clamp(MIN_CELL, MAX_CELL)
+            .floor();
+        let (rect, response) = ui.allocate_exact_size(
+            Vec2::new(side * max_x as f32, side * max_y as f32),
+            Sense::click(),
+        );
+        let hovered = response.hover_pos().and_then(|pos| {
+            let offset = (pos - rect.min) / side;
+            let (x, y) = (offset.x.floor(), offset.y.floor());
+            (x >= 0.0 && y >= 0.0 && (x as usize) < max_x && (y as usize) < max_y)
+                .then_some((x as usize, y as usize))
+        });
+        if !ui.is_rect_visible(rect) {
+            return BoardResponse { response, hovered };
+        }
+
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals();
+        for y in 0..max_y {
+            for x in 0..max_x {
+                let cell_rect = Rect::from_min_size(
+                    rect.min + Vec2::new(x as f32, y as f32) * side,
+                    Vec2::splat(side),
+                )
+                .shrink(side * GAP);
+                let cell = self.board.get((x, y));
+                let painted = Painted {
+                    rect: cell_rect,
+                    side,
+                    cell,
+                    style: self.style,
+                };
+                painter.rect_filled(cell_rect, 0.0, self.background(cell, (x, y), visuals));
+                if let Some(preview) = self.preview {
+                    painted.preview(&painter, preview, (x, y));
+                }
+                painted.cell(&painter);
+                if self.highlighted == Some((x, y)) {
+                    painter.rect_stroke(cell_rect, 0.0, Stroke::new(2.0, Color32::GOLD));
+                } else if hovered == Some((x, y)) {
+                    painter.rect_stroke(cell_rect, 0.0, visuals.widgets.hovered.bg_stroke);
+                }
+            }
+        }
+        if self.style.connections {
+            self.connections(&painter, rect, side);
+        }
+        BoardResponse { response, hovered }
+    }
+    /// Lines between the centres of linked filled cells, group by group.
+    fn connections(&self, painter: &egui::Painter, rect: Rect, side: f32) {
+        let center = |(x, y): (usize, usize)| {
+            rect.min + (Vec2::new(x as f32, y as f32) + Vec2::splat(0.5)) * side
+        };
+        let mut grouped = vec![false; self.board.board.len()];
+        for y in 0..self.board.max_y {
+            for x in 0..self.board.max_x {
+                let cell = self.board.get((x, y));
+                if cell.kind() != CellKind::Filled || grouped[y * self.board.max_x + x] {
+                    continue;
+                }
+                grouped[y * self.board.max_x + x] = true;
+                let color = if cell.is_alive() {
+                    Color32::WHITE
+                } else {
+                    Color32::BLACK
+                };
+                let stroke = Stroke::new(side * 0.08, color.gamma_multiply(0.8));
+                for (from, to) in self.board.group_links((x, y)) {
+                    grouped[to.1 * self.board.max_x + to.0] = true;
+                    painter.line_segment([center(from), center(to)], stroke);
+                }
+                painter.circle_filled(center((x, y)), side * 0.1, color);
+            }
+        }
+    }
+    fn background(
+        &self,
+        cell: SampleCell,
+        index: (usize, usize),
+        visuals: &egui::Visuals,
+    ) -> Color32 {
+        match cell.kind() {
+            CellKind::Filled => {
+                let color = player_color(cell.player());
+                // Dead groups no longer count for their owner, so they fade.
+                if cell.is_alive() {
+                    color
+                } else {
+                    color.gamma_multiply(0.35)
+                }
+            }
+            CellKind::Border => Color32::DARK_GRAY,
+            CellKind::Empty | CellKind::Cross => {
+                let heat = self
+                    .heat
+                    .and_then(|heat| heat[index.1 * self.board.max_x + index.0]);
+                if cell.is_checked() {
+                    Color32::GOLD
+                } else if let Some(heat) = heat {
+                    let strength = 0.1 + 0.2 * heat.margin as f32;
+                    player_color(heat.player).gamma_multiply(strength)
+                } else if cell.is_active(self.player) {
+                    Color32::GRAY
+                } else {
+                    visuals.widgets.inactive.bg_fill
+                }
+            }
+        }
+    }
+}
+
+/// One cell being painted.
+struct Painted {
+    rect: Rect,
+    side: f32,
+    cell: SampleCell,
+    style: BoardStyle,
+}
+impl Painted {
+    /// The cross, and markers for deadness, importance and overheat.
+    fn cell(&self, painter: &egui::Painter) {
+        let cell = self.cell;
+        if self.style.hatch_dead && cell.kind() == CellKind::Filled && !cell.is_alive() {
+            self.hatch(painter, player_color(cell.player()));
+        }
+        if cell.kind() == CellKind::Cross {
+            let cross = self.rect.shrink(self.side * 0.2);
+            let stroke = Stroke::new(self.side * 0.1, player_color(cell.player()));
+            painter.line_segment([cross.left_top(), cross.right_bottom()], stroke);
+            painter.line_segment([cross.right_top(), cross.left_bottom()], stroke);
+        }
+        let important =
+            matches!(cell.kind(), CellKind::Cross | CellKind::Filled) && cell.is_important();
+        if important && self.style.outline_important {
+            let stroke = Stroke::new(
+                self.side * 0.08,
+                painter.ctx().style().visuals.strong_text_color(),
+            );
+            painter.rect_stroke(self.rect.shrink(self.side * 0.04), 0.0, stroke);
+        } else if important {
+            let radius = self.side * 0.1;
+            let center = self.rect.right_top() + Vec2::new(-radius * 1.5, radius * 1.5);
+            painter.circle(
+                center,
+                radius,
+                Color32::WHITE,
+                Stroke::new(1.0, Color32::BLACK),
+            );
+        }
+        if matches!(cell.kind(), CellKind::Empty | CellKind::Cross) && cell.is_overheated() {
+            let corner = self.rect.left_bottom();
+            let size = self.side * 0.3;
+            painter.add(Shape::convex_polygon(
+                vec![
+                    corner,
+                    corner + Vec2::new(size, 0.0),
+                    corner + Vec2::new(0.0, -size),
+                ],
+                Color32::from_rgb(0xff, 0x60, 0x00),
+                Stroke::NONE,
+            ));
+        }
+    }
+    /// Diagonal lines across the cell.
+    fn hatch(&self, painter: &egui::Painter, color: Color32) {
+        let painter = painter.with_clip_rect(self.rect);
+        let stroke = Stroke::new(self.side * 0.06, color);
+        let step = self.side * HATCH_STEP;
+        let mut offset = step;
+        while offset < self.side * 2.0 {
+            painter.line_segment(
+                [
+                    self.rect.left_top() + Vec2::new(offset, 0.0),
+                    self.rect.left_top() + Vec2::new(0.0, offset),
+                ],
+                stroke,
+            );
+            offset += step;
+        }
+    }
+    /// What a move would do to this cell.
+    fn preview(&self, painter: &egui::Painter, preview: &MovePreview, index: (usize, usize)) {
+        let color = player_color(preview.player);
+        if index == preview.index {
+            let strength = match preview.kind {
+                MoveKind::Cross => 0.3,
+                MoveKind::Fill => 0.6,
+            };
+            painter.rect_filled(self.rect, 0.0, color.gamma_multiply(strength));
+            return;
+        }
+        if preview.activated.contains(&index) {
+            painter.rect_filled(self.rect, 0.0, color.gamma_multiply(0.25));
+        }
+        if preview.deactivated.contains(&index) {
+            painter.rect_filled(self.rect, 0.0, Color32::DARK_GRAY);
+        }
+        let stroke = if preview.killed.contains(&index) {
+            Stroke::new(2.0, Color32::BLACK)
+        } else if preview.lost_importance.contains(&index) {
+            Stroke::new(2.0, Color32::YELLOW)
+        } else {
+            return;
+        };
+        painter.rect_stroke(self.rect.shrink(1.0), 0.0, stroke);
+    }
+}
@@ -0,0 +1,201 @@
+//! Hosting a game or joining one over the network, see `crate::network`.
+//!
+//! The host plays the seats that aren't marked as remote, guests take the
+//! remote ones in the order they join. A guest never changes its game by
+//! itself: its moves go to the host and come back once the host made them.
+//! Guests that join to watch, or find no free seat, are spectators.
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::{player_name, TemplateApp};
+use crate::network::{Guest, Host, Notice, DEFAULT_PORT};
+use crate::sample_core::sample_board_manager::MAX_PLAYERS;
+
+/// How many network messages the window keeps.
+const LOG_SIZE: usize = 50;
+
+pub enum Session {
+    Host(Host),
+    Guest(Guest),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NetworkSettings {
+    /// `host:port` to join. Hosting only uses the port.
+    address: String,
+    /// Seats played by guests when hosting.
+    remote: [bool; MAX_PLAYERS],
+    /// Join without taking a seat.
+    #[serde(default)]
+    watch: bool,
+}
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            address: format!("127.0.0.1:{DEFAULT_PORT}"),
+            remote: [false, true, false, false],
+            watch: false,
+        }
+    }
+}
+impl NetworkSettings {
+    fn port(&self) -> Option<u16> {
+        match self.address.rsplit_once(':') {
+            Some((_, port)) => port.trim().parse().ok(),
+            None => Some(DEFAULT_PORT),
+        }
+    }
+}
+
+impl TemplateApp {
+    pub(super) fn is_guest(&self) -> bool {
+        matches!(self.session, Some(Session::Guest(_)))
+    }
+    pub(super) fn is_spectator(&self) -> bool {
+        match &self.session {
+            Some(Session::Guest(guest)) => guest.is_connected() && guest.seat().is_none(),
+            _ => false,
+        }
+    }
+    /// The position a guest's host is at, as opposed to where the guest is looking.
+    pub(super) fn live_move(&self) -> Option<usize> {
+        match &self.session {
+            Some(Session::Guest(guest)) => guest.live(),
+            _ => None,
+        }
+    }
+    /// Whether moves for `player` are made on this instance, by a human or a bot.
+    pub(super) fn is_local_player(&self, player: usize) -> bool {
+        match &self.session {
+            None => true,
+            Some(Session::Host(host)) => !host.is_remote(player),
+            Some(Session::Guest(guest)) => guest.seat() == Some(player),
+        }
+    }
+    /// Makes a move, or asks the host for it when playing as a guest.
+    pub(super) fn play(&mut self, x: usize, y: usize) {
+        self.game.board_manager.clear_checked();
+        let result = match &mut self.session {
+            Some(Session::Guest(guest)) => guest.send_move((x, y)).map_err(|e| e.to_string()),
+            _ => self.game.make_move(x, y).map_err(|e| e.to_string()),
+        };
+        if let Err(e) = result {
+            self.current_error = e;
+            self.bot_sent = None;
+        }
+    }
+    /// Exchanges moves with the other side.
+    pub(super) fn network_step(&mut self) {
+        let notices = match &mut self.session {
+            None => return,
+            Some(Session::Host(host)) => host.update(&mut self.game),
+            Some(Session::Guest(guest)) => guest.update(&mut self.game),
+        };
+        for notice in notices {
+            log::info!("{notice}");
+            if let Notice::Rejected(_) | Notice::Error(_) = notice {
+                self.current_error = notice.to_string();
+                // A rejected bot move won't be echoed, let the bot choose again.
+                self.bot_sent = None;
+            }
+            if self.network_log.len() == LOG_SIZE {
+                self.network_log.remove(0);
+            }
+            self.network_log.push(notice.to_string());
+        }
+    }
+    pub(super) fn network_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.network_open;
+        egui::Window::new("Сеть")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                match &self.session {
+                    None => self.network_setup(ui, ctx),
+                    Some(Session::Host(host)) => {
+                        ui.label(format!("Игра создана, порт {}", host.port()));
+                        let guests = host.guests();
+                        if guests.is_empty() {
+                            ui.label("Ждём соперников…");
+                        }
+                        for (address, seat) in guests {
+                            ui.label(match seat {
+                                Some(seat) => format!("{address} — за {}", player_name(seat)),
+                                None => format!("{address} — смотрит"),
+                            });
+                        }
+                    }
+                    Some(Session::Guest(guest)) => {
+                        ui.label(format!("Игра на {}", guest.address()));
+                        ui.label(match (guest.is_connected(), guest.seat()) {
+                            (false, _) => "Подключаемся…".to_string(),
+                            (true, Some(seat)) => format!("Играем за {}", player_name(seat)),
+                            (true, None) => "Смотрим".to_string(),
+                        });
+                    }
+                }
+                let looking_back = self
+                    .live_move()
+                    .is_some_and(|live| live != self.game.player_manager.current_move());
+                if looking_back && ui.button("К текущему ходу").clicked() {
+                    self.close_replay();
+                }
+                if self.session.is_some() && ui.button("Отключиться").clicked() {
+                    self.session = None;
+                    self.network_log.clear();
+                }
+                if !self.network_log.is_empty() {
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .max_height(120.0)
+                        .stick_to_bottom(true)
+                        .show(ui, |ui| {
+                            for line in &self.network_log {
+                                ui.monospace(line);
+                            }
+                        });
+                }
+            });
+        self.network_open = open;
+    }
+    fn network_setup(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let settings = &mut self.network;
+        ui.horizontal(|ui| {
+            ui.label("Адрес:");
+            ui.text_edit_singleline(&mut settings.address)
+                .on_hover_text("Куда подключаться. Для своей игры важен только порт");
+        });
+        ui.label("По сети играют за:");
+        ui.horizontal(|ui| {
+            for player in 0..self.game.settings.players() {
+                ui.checkbox(&mut settings.remote[player], player_name(player));
+            }
+        });
+        let ctx = ctx.clone();
+        let wake = Arc::new(move || ctx.request_repaint());
+        ui.horizontal(|ui| {
+            if ui.button("Создать игру").clicked() {
+                let seats = (0..self.game.settings.players())
+                    .filter(|&player| settings.remote[player])
+                    .collect();
+                let started = match settings.port() {
+                    Some(port) => Host::start(port, seats, &self.game, wake.clone())
+                        .map_err(|e| e.to_string()),
+                    None => Err(format!("Непонятный порт в адресе «{}»", settings.address)),
+                };
+                match started {
+                    Ok(host) => self.session = Some(Session::Host(host)),
+                    Err(e) => self.current_error = e,
+                }
+            }
+            if ui.button("Подключиться").clicked() {
+                let address = settings.address.trim().to_string();
+                let guest = Guest::connect(address, settings.watch, wake);
+                self.session = Some(Session::Guest(guest));
+                self.replay = None;
+            }
+            ui.checkbox(&mut settings.watch, "Только смотреть");
+        });
+    }
+}
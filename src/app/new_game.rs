@@ -0,0 +1,236 @@
+//! The new-game window: board size, moves per turn, the clock and the starting layout.
+//!
+//! Pre-filled cells and obstacles are typed as lists of cells in move
+//! notation, e.g. `c3 d4`. The text is kept as typed and only parsed when
+//! the game is started, so half-typed cells don't get in the way.
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{player_name, TemplateApp};
+use crate::sample_core::{
+    layout::{Layout, Preset},
+    move_notation::{format_index, parse_index},
+    sample_board_manager::{MAX_PLAYERS, MAX_SIDE, MIN_PLAYERS, MIN_SIDE},
+    CrossesCore,
+};
+
+/// Cells of the layout being edited, as typed.
+#[derive(Default, Serialize, Deserialize)]
+pub struct LayoutText {
+    fills: [String; MAX_PLAYERS],
+    obstacles: String,
+}
+impl LayoutText {
+    fn new(layout: &Layout) -> Self {
+        let mut text = Self {
+            obstacles: format_cells(layout.obstacles.iter().copied()),
+            ..Default::default()
+        };
+        for (player, fills) in text.fills.iter_mut().enumerate() {
+            *fills = format_cells(
+                layout
+                    .fills
+                    .iter()
+                    .filter(|&&(_, owner)| owner == player)
+                    .map(|&(index, _)| index),
+            );
+        }
+        text
+    }
+    /// Puts the typed cells into `layout`. Fills of players who don't play are dropped.
+    fn apply(&self, layout: &mut Layout) -> Result<(), String> {
+        let mut fills = Vec::new();
+        for (player, text) in self.fills.iter().enumerate().take(layout.players()) {
+            fills.extend(parse_cells(text)?.into_iter().map(|index| (index, player)));
+        }
+        layout.fills = fills;
+        layout.obstacles = parse_cells(&self.obstacles)?;
+        Ok(())
+    }
+    /// Cells typed so far for the first `players` players and as obstacles,
+    /// skipping the ones that don't parse yet.
+    fn cells(&self, players: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.fills
+            .iter()
+            .take(players)
+            .chain([&self.obstacles])
+            .flat_map(|text| text.split_whitespace())
+            .filter_map(parse_index)
+    }
+}
+
+fn format_cells(cells: impl Iterator<Item = (usize, usize)>) -> String {
+    cells.map(format_index).collect::<Vec<_>>().join(" ")
+}
+
+/// The cell of the board closest to `target` that isn't `taken`.
+fn nearest_free(
+    target: (usize, usize),
+    (max_x, max_y): (usize, usize),
+    taken: &[(usize, usize)],
+) -> Option<(usize, usize)> {
+    (0..max_y)
+        .flat_map(|y| (0..max_x).map(move |x| (x, y)))
+        .filter(|cell| !taken.contains(cell))
+        .min_by_key(|&(x, y)| x.abs_diff(target.0).max(y.abs_diff(target.1)))
+}
+
+fn parse_cells(text: &str) -> Result<Vec<(usize, usize)>, String> {
+    text.split_whitespace()
+        .map(|token| parse_index(token).ok_or_else(|| format!("Непонятная клетка «{token}»")))
+        .collect()
+}
+
+fn preset_name(preset: Option<Preset>) -> &'static str {
+    match preset {
+        Some(Preset::Corners) => "По углам",
+        Some(Preset::Edges) => "По краям",
+        Some(Preset::Centre) => "Вокруг центра",
+        None => "Своя",
+    }
+}
+
+impl TemplateApp {
+    pub(super) fn open_new_game(&mut self) {
+        self.new_game = self.game.settings.clone();
+        self.new_game_text = LayoutText::new(&self.new_game.layout);
+        self.new_game_open = true;
+    }
+    pub(super) fn new_game_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.new_game_open;
+        let mut start = false;
+        egui::Window::new("Новая игра")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let settings = &mut self.new_game;
+                let text = &mut self.new_game_text;
+                let preset =
+                    Preset::recognize(&settings.layout.starts, settings.max_x, settings.max_y)
+                        .filter(|_| text.fills.iter().all(String::is_empty))
+                        .filter(|_| text.obstacles.is_empty());
+                let mut players = settings.players();
+                let size = (settings.max_x, settings.max_y);
+                egui::Grid::new("NewGame").show(ui, |ui| {
+                    ui.label("Ширина:");
+                    ui.add(egui::DragValue::new(&mut settings.max_x).range(MIN_SIDE..=MAX_SIDE));
+                    ui.end_row();
+                    ui.label("Высота:");
+                    ui.add(egui::DragValue::new(&mut settings.max_y).range(MIN_SIDE..=MAX_SIDE));
+                    ui.end_row();
+                    ui.label("Ходов за раз:");
+                    ui.add(egui::DragValue::new(&mut settings.moves_per_turn).range(1..=16));
+                    ui.end_row();
+                    ui.label("Часы:");
+                    ui.horizontal(|ui| {
+                        let mut timed = settings.time_control.is_some();
+                        ui.checkbox(&mut timed, "")
+                            .on_hover_text("Кончилось время — проиграл");
+                        if timed != settings.time_control.is_some() {
+                            settings.time_control = timed.then(Default::default);
+                        }
+                        if let Some(control) = &mut settings.time_control {
+                            let mut minutes = control.total.as_secs() / 60;
+                            ui.add(
+                                egui::DragValue::new(&mut minutes)
+                                    .range(1..=180)
+                                    .suffix(" мин"),
+                            );
+                            control.total = Duration::from_secs(minutes * 60);
+                            let mut increment = control.increment.as_secs();
+                            ui.add(
+                                egui::DragValue::new(&mut increment)
+                                    .range(0..=60)
+                                    .prefix("+")
+                                    .suffix(" с за ход"),
+                            );
+                            control.increment = Duration::from_secs(increment);
+                        }
+                    });
+                    ui.end_row();
+                    ui.label("Игроков:");
+                    ui.add(egui::DragValue::new(&mut players).range(MIN_PLAYERS..=MAX_PLAYERS));
+                    ui.end_row();
+
+                    ui.label("Расстановка:");
+                    let mut chosen = preset;
+                    egui::ComboBox::from_id_source("NewGameLayout")
+                        .selected_text(preset_name(preset))
+                        .show_ui(ui, |ui| {
+                            for option in Preset::ALL {
+                                ui.selectable_value(
+                                    &mut chosen,
+                                    Some(option),
+                                    preset_name(Some(option)),
+                                );
+                            }
+                        });
+                    ui.end_row();
+                    // A preset follows the board size and the number of players,
+                    // a custom layout keeps its cells and only gains or loses starts.
+                    let resized = size != (settings.max_x, settings.max_y);
+                    if let Some(chosen) = chosen.filter(|&chosen| {
+                        Some(chosen) != preset || resized || players != settings.players()
+                    }) {
+                        settings.layout =
+                            Layout::preset(chosen, settings.max_x, settings.max_y, players);
+                        *text = LayoutText::default();
+                    } else if players != settings.players() {
+                        let board = (settings.max_x, settings.max_y);
+                        let corners = Preset::Corners.starts(board.0, board.1);
+                        let starts = &mut settings.layout.starts;
+                        starts.truncate(players);
+                        // New players start in the corners, or as close to them as
+                        // the cells the layout already takes allow. A board with no
+                        // room left keeps the corner and "Начать" reports the overlap.
+                        let mut taken: Vec<_> =
+                            starts.iter().copied().chain(text.cells(players)).collect();
+                        for &corner in &corners[starts.len()..players] {
+                            let start = nearest_free(corner, board, &taken).unwrap_or(corner);
+                            taken.push(start);
+                            starts.push(start);
+                        }
+                    }
+
+                    for player in 0..settings.players() {
+                        ui.label(format!("Старт {}:", player_name(player)));
+                        ui.horizontal(|ui| {
+                            let (x, y) = &mut settings.layout.starts[player];
+                            ui.label("x:");
+                            ui.add(egui::DragValue::new(x).range(0..=settings.max_x - 1));
+                            ui.label("y:");
+                            ui.add(egui::DragValue::new(y).range(0..=settings.max_y - 1));
+                        });
+                        ui.end_row();
+                    }
+                    for player in 0..settings.players() {
+                        ui.label(format!("Закрашено у {}:", player_name(player)));
+                        ui.text_edit_singleline(&mut text.fills[player])
+                            .on_hover_text("Клетки через пробел, например c3 d4");
+                        ui.end_row();
+                    }
+                    ui.label("Препятствия:");
+                    ui.text_edit_singleline(&mut text.obstacles)
+                        .on_hover_text("Клетки через пробел, в них нельзя ходить");
+                    ui.end_row();
+                });
+                start = ui.button("Начать").clicked();
+            });
+        if start {
+            let mut settings = self.new_game.clone();
+            let game = self
+                .new_game_text
+                .apply(&mut settings.layout)
+                .and_then(|()| CrossesCore::new(settings).map_err(|e| e.to_string()));
+            match game {
+                Ok(game) => {
+                    self.load_game(game);
+                    open = false;
+                }
+                Err(e) => self.current_error = e,
+            }
+        }
+        self.new_game_open = open;
+    }
+}
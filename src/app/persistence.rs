@@ -0,0 +1,283 @@
+//! Restoring `TemplateApp` from eframe storage.
+//!
+//! Every saved state carries `version`. States written before versioning existed
+//! have no such field and are treated as version 0.
+use crosses_core::player_manager::{LoseData, PlayerManager};
+use serde::Deserialize;
+
+use super::TemplateApp;
+use crate::sample_core::{
+    bot::Difficulty,
+    game_tree::GameTree,
+    layout::{Layout, Preset},
+    sample_board_manager::MAX_PLAYERS,
+    CrossesCore, CrossesError, GameSettings,
+};
+
+/// Bump this whenever the persisted layout of `TemplateApp` changes,
+/// and teach `restore` how to migrate the previous version.
+pub const STATE_VERSION: u32 = 6;
+
+#[derive(Deserialize)]
+struct StateVersion {
+    #[serde(default)]
+    version: u32,
+}
+
+/// The parts of older states we still understand.
+#[derive(Deserialize)]
+struct LegacyState {
+    game: LegacyGame,
+}
+#[derive(Deserialize)]
+struct LegacyGame {
+    board_manager: LegacyBoard,
+    /// Missing before version 1.
+    #[serde(default)]
+    settings: Option<LegacySettings>,
+    log: Vec<(usize, usize)>,
+}
+/// Settings before starting layouts: a fixed array of starts, of which
+/// the first `players` were used. Version 1 only knew two players.
+#[derive(Deserialize)]
+struct LegacySettings {
+    moves_per_turn: usize,
+    #[serde(default)]
+    players: Option<usize>,
+    starts: LegacyStarts,
+}
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LegacyStarts {
+    Two([(usize, usize); 2]),
+    All([(usize, usize); MAX_PLAYERS]),
+}
+impl LegacySettings {
+    fn layout(&self) -> Layout {
+        let starts: &[(usize, usize)] = match &self.starts {
+            LegacyStarts::Two(starts) => starts,
+            LegacyStarts::All(starts) => starts,
+        };
+        let players = self.players.unwrap_or(2).min(starts.len());
+        Layout {
+            starts: starts[..players].to_vec(),
+            ..Default::default()
+        }
+    }
+}
+/// Versions 2 to 4 differ from the current state only in settings.
+/// The fields after `game` are kept as they were.
+#[derive(Deserialize)]
+struct StartsState {
+    game: StartsGame,
+    #[serde(default)]
+    new_game: Option<StartsNewGame>,
+    #[serde(default)]
+    export_field: String,
+    #[serde(default)]
+    import_field: String,
+    #[serde(default)]
+    new_game_open: bool,
+    #[serde(default)]
+    focused: Option<(usize, usize)>,
+    #[serde(default)]
+    bots: [Option<Difficulty>; MAX_PLAYERS],
+}
+#[derive(Deserialize)]
+struct StartsGame {
+    board_manager: LegacyBoard,
+    player_manager: PlayerManager<Vec<Option<LoseData>>>,
+    settings: LegacySettings,
+    log: Vec<(usize, usize)>,
+    /// Missing in version 2.
+    #[serde(default)]
+    tree: Option<GameTree>,
+}
+/// Settings of the next game in versions 2 to 4.
+#[derive(Deserialize)]
+struct StartsNewGame {
+    max_x: usize,
+    max_y: usize,
+    moves_per_turn: usize,
+    players: usize,
+    starts: [(usize, usize); MAX_PLAYERS],
+}
+#[derive(Deserialize)]
+struct LegacyBoard {
+    max_x: usize,
+    max_y: usize,
+}
+impl LegacyState {
+    /// Unversioned builds always played 4 moves per turn from the corners
+    /// and had no undo, so replaying the log rebuilds the game. Version 1
+    /// cells can't be read any more, so its log is replayed as well,
+    /// including moves that were undone.
+    fn migrate(self) -> Result<CrossesCore, CrossesError> {
+        let LegacyBoard { max_x, max_y } = self.game.board_manager;
+        let (layout, moves_per_turn) = match self.game.settings {
+            Some(settings) => (settings.layout(), settings.moves_per_turn),
+            None => (
+                Layout::preset(Preset::Corners, max_x.max(1), max_y.max(1), 2),
+                4,
+            ),
+        };
+        let mut game = CrossesCore::new(GameSettings {
+            max_x,
+            max_y,
+            moves_per_turn,
+            layout,
+            time_control: None,
+        })?;
+        for (x, y) in self.game.log {
+            game.make_move(x, y)?;
+        }
+        Ok(game)
+    }
+}
+impl StartsState {
+    /// The whole line is replayed under the new settings and then
+    /// taken back to where the player was.
+    fn migrate(self) -> Result<TemplateApp, CrossesError> {
+        let LegacyBoard { max_x, max_y } = self.game.board_manager;
+        let mut game = CrossesCore::new(GameSettings {
+            max_x,
+            max_y,
+            moves_per_turn: self.game.settings.moves_per_turn,
+            layout: self.game.settings.layout(),
+            time_control: None,
+        })?;
+        for &(x, y) in &self.game.log {
+            game.make_move(x, y)?;
+        }
+        if let Some(tree) = self.game.tree {
+            game.tree = tree;
+            game.tree.insert(&game.log);
+        }
+        game.seek(self.game.player_manager.current_move())?;
+        Ok(TemplateApp {
+            game,
+            new_game: self
+                .new_game
+                .map(StartsNewGame::migrate)
+                .unwrap_or_default(),
+            export_field: self.export_field,
+            import_field: self.import_field,
+            new_game_open: self.new_game_open,
+            focused: self.focused,
+            bots: self.bots,
+            ..Default::default()
+        })
+    }
+}
+impl StartsNewGame {
+    fn migrate(self) -> GameSettings {
+        GameSettings {
+            max_x: self.max_x,
+            max_y: self.max_y,
+            moves_per_turn: self.moves_per_turn,
+            layout: Layout {
+                starts: self.starts[..self.players.min(MAX_PLAYERS)].to_vec(),
+                ..Default::default()
+            },
+            time_control: None,
+        }
+    }
+}
+
+/// Deserializing checks every part of the game on its own, not that they fit
+/// together, and a board shorter than its size panics on the first lookup.
+fn is_intact(game: &CrossesCore) -> bool {
+    let board = &game.board_manager;
+    let players = game.settings.players();
+    game.settings.moves_per_turn > 0
+        && (board.max_x, board.max_y) == (game.settings.max_x, game.settings.max_y)
+        && board.board.len() == board.max_x * board.max_y
+        && board.moves_counter.len() == players
+        && board.crosses_counter.len() == players
+        && game
+            .clock
+            .as_ref()
+            .map_or(true, |clock| clock.remaining.len() == players)
+        && game
+            .flags
+            .iter()
+            .all(|&(player, at)| player < players && at <= game.log.len())
+        && game.player_manager.current_move() <= game.log.len()
+        && game
+            .log
+            .iter()
+            .all(|&(x, y)| x < board.max_x && y < board.max_y)
+}
+
+/// The tree isn't checked while deserializing either. One whose links are
+/// broken or whose moves don't replay is replaced by the log alone, the way
+/// version 2 games are migrated. Returns whether the tree had to be replaced,
+/// or `None` if the log doesn't replay either.
+fn repair_tree(game: &mut CrossesCore) -> Option<bool> {
+    if game.tree.is_consistent() && game.notation().is_ok() {
+        game.tree.insert(&game.log);
+        return Some(false);
+    }
+    game.tree = Default::default();
+    game.tree.insert(&game.log);
+    game.notation().ok().map(|_| true)
+}
+
+pub fn restore(storage: &dyn eframe::Storage) -> TemplateApp {
+    let Some(StateVersion { version }) = eframe::get_value(storage, eframe::APP_KEY) else {
+        return Default::default();
+    };
+    let message = match version {
+        // Version 5 had no clocks, its games simply aren't timed.
+        5 | STATE_VERSION => match eframe::get_value::<TemplateApp>(storage, eframe::APP_KEY) {
+            Some(mut app) if is_intact(&app.game) => match repair_tree(&mut app.game) {
+                Some(repaired) => {
+                    if repaired {
+                        app.current_error =
+                            "Варианты сохранённой игры повреждены, осталась текущая линия"
+                                .to_string();
+                        log::warn!("{}", app.current_error);
+                    }
+                    return TemplateApp {
+                        version: STATE_VERSION,
+                        ..app
+                    };
+                }
+                None => "Сохранённая игра повреждена, начата новая".to_string(),
+            },
+            _ => "Сохранённая игра повреждена, начата новая".to_string(),
+        },
+        // Versions 2 to 4 stored starts instead of a layout. Version 2 had no tree
+        // of variations, its log becomes the only line.
+        2..=4 => match eframe::get_value::<StartsState>(storage, eframe::APP_KEY)
+            .map(StartsState::migrate)
+        {
+            Some(Ok(app)) => return app,
+            Some(Err(e)) => format!("Не удалось перенести игру из старой версии: {e}"),
+            None => "Сохранённая игра повреждена, начата новая".to_string(),
+        },
+        0 | 1 => match eframe::get_value::<LegacyState>(storage, eframe::APP_KEY)
+            .map(LegacyState::migrate)
+        {
+            Some(Ok(game)) => {
+                let mut app = TemplateApp {
+                    game,
+                    ..Default::default()
+                };
+                app.current_error = format!(
+                    "Игра {}x{} перенесена из старой версии",
+                    app.game.board_manager.max_x, app.game.board_manager.max_y
+                );
+                return app;
+            }
+            Some(Err(e)) => format!("Не удалось перенести игру из старой версии: {e}"),
+            None => "Сохранение старой версии не распознано, начата новая игра".to_string(),
+        },
+        newer => format!("Сохранение версии {newer} новее программы, начата новая игра"),
+    };
+    log::warn!("{message}");
+    TemplateApp {
+        current_error: message,
+        ..Default::default()
+    }
+}
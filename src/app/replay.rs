@@ -0,0 +1,141 @@
+//! Reviewing a game by scrubbing through its log.
+//!
+//! The replay never edits the log: it only moves the cursor with
+//! `CrossesCore::seek`, so leaving it keeps the position that was reached.
+//! A network guest is the exception, it goes back to the live position.
+//! While it looks back, moves made on the host still reach its log.
+use super::TemplateApp;
+use crate::sample_core::move_notation::format_index;
+
+/// Playback speed in moves per second.
+const SPEEDS: std::ops::RangeInclusive<f64> = 0.5..=20.0;
+
+pub struct Replay {
+    playing: bool,
+    speed: f64,
+    next_step_at: f64,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self {
+            playing: false,
+            speed: 2.0,
+            next_step_at: 0.0,
+        }
+    }
+}
+
+impl TemplateApp {
+    pub(super) fn open_replay(&mut self, from_start: bool) {
+        if from_start {
+            self.seek(0);
+        }
+        self.replay = Some(Replay::default());
+    }
+    pub(super) fn close_replay(&mut self) {
+        self.replay = None;
+        if let Some(live) = self.live_move() {
+            self.seek(live);
+        }
+    }
+    /// Moves one step forward while the replay is playing.
+    pub(super) fn replay_step(&mut self, ctx: &egui::Context) {
+        // A guest may get a new timed game from the host while looking back.
+        // It comes at the end of its log, where telling whether it's over is cheap.
+        if self.replay.is_some() && !self.game.can_forward() && !self.can_review() {
+            self.close_replay();
+            return;
+        }
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        if !replay.playing {
+            return;
+        }
+        if !self.game.can_forward() {
+            replay.playing = false;
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let step = now >= replay.next_step_at;
+        if step {
+            replay.next_step_at = now + 1.0 / replay.speed;
+        }
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(
+            replay.next_step_at - now,
+        ));
+        if step {
+            self.redo();
+        }
+    }
+    pub(super) fn replay_panel(&mut self, ui: &mut egui::Ui) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        let total = self.game.log.len();
+        let mut position = self.game.player_manager.current_move();
+        let progress = match self.game.last_move() {
+            Some(index) => format!("Ход {position} из {total}: {}", format_index(index)),
+            None => format!("Ход {position} из {total}"),
+        };
+        let mut close = false;
+        ui.horizontal(|ui| {
+            ui.label("Разбор");
+            ui.separator();
+            if ui
+                .add_enabled(position != 0, egui::Button::new("⏮"))
+                .on_hover_text("В начало")
+                .clicked()
+            {
+                position = 0;
+            }
+            let play_label = if replay.playing { "⏸" } else { "▶" };
+            if ui
+                .add_enabled(position != total, egui::Button::new(play_label))
+                .clicked()
+            {
+                replay.playing = !replay.playing;
+                replay.next_step_at = 0.0;
+            }
+            if ui
+                .add_enabled(position != total, egui::Button::new("⏭"))
+                .on_hover_text("В конец")
+                .clicked()
+            {
+                position = total;
+            }
+            let slider = ui.add(
+                egui::Slider::new(&mut position, 0..=total)
+                    .show_value(false)
+                    .step_by(1.0),
+            );
+            if slider.dragged() {
+                replay.playing = false;
+            }
+            ui.label(progress);
+            ui.separator();
+            ui.label("Скорость:");
+            ui.add(
+                egui::DragValue::new(&mut replay.speed)
+                    .range(SPEEDS)
+                    .speed(0.1)
+                    .suffix(" ход/с"),
+            );
+            ui.separator();
+            close = ui.button("Закрыть").clicked();
+        });
+        if position != self.game.player_manager.current_move() {
+            self.seek(position);
+        }
+        if close {
+            self.close_replay();
+        }
+    }
+    fn seek(&mut self, target: usize) {
+        self.game.board_manager.clear_checked();
+        if let Err(e) = self.game.seek(target) {
+            self.current_error = e.to_string();
+        }
+    }
+}
@@ -0,0 +1,224 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+//! Plays a game in the terminal without a window: prints the board as text
+//! and reads moves and commands from stdin, one per line.
+
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use crosses_core::board_manager::BoardManager;
+use crosses_core::player_manager::GameState;
+use crosses_egui::sample_core::{
+    bot::{Bot, Difficulty},
+    layout::{Layout, Preset},
+    move_notation::{column_name, format_index, parse_index},
+    sample_board_manager::{MAX_PLAYERS, MIN_PLAYERS, PLAYER_COLORS},
+    save_format::cell_to_char,
+    CrossesCore, GameSettings,
+};
+
+const USAGE: &str = "\
+usage: crosses-cli [--size WxH] [--turn N] [--players N] [--layout LAYOUT] [--COLOR BOT]...
+
+  --size WxH     board size, 10x10 by default
+  --turn N       moves per turn, 4 by default
+  --players N    2 to 4 players, 2 by default
+  --layout L     where players start: corners, edges or centre,
+                 corners by default
+  --COLOR BOT    let a bot play for blue, red, green or yellow:
+                 easy, medium or hard";
+
+const HELP: &str = "\
+c3 or 2,2   make a move (columns are letters, rows start at 1)
+undo, redo  step back or forward through the log
+variations  list the moves played from this position, main line first
+line c3     continue the log with the variation starting at c3
+promote     make the current line the main one
+moves       print the moves made so far, with all variations
+export      print the game in the save format
+quit        stop playing";
+
+struct Options {
+    settings: GameSettings,
+    bots: [Option<Difficulty>; MAX_PLAYERS],
+}
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            settings: GameSettings::default(),
+            bots: Default::default(),
+        };
+        let mut players = options.settings.players();
+        let mut preset = Preset::Corners;
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or_else(|| format!("{flag} needs a value"))?;
+            let bad_value = || format!("bad value for {flag}: \"{value}\"");
+            match flag.as_str() {
+                "--size" => {
+                    let (max_x, max_y) = value.split_once('x').ok_or_else(bad_value)?;
+                    options.settings.max_x = max_x.parse().map_err(|_| bad_value())?;
+                    options.settings.max_y = max_y.parse().map_err(|_| bad_value())?;
+                }
+                "--turn" => {
+                    options.settings.moves_per_turn = value.parse().map_err(|_| bad_value())?;
+                }
+                "--players" => {
+                    players = value.parse().map_err(|_| bad_value())?;
+                }
+                "--layout" => {
+                    preset = match value.as_str() {
+                        "corners" => Preset::Corners,
+                        "edges" => Preset::Edges,
+                        "centre" | "center" => Preset::Centre,
+                        _ => return Err(bad_value()),
+                    };
+                }
+                "--blue" | "--red" | "--green" | "--yellow" => {
+                    let difficulty = match value.as_str() {
+                        "easy" => Difficulty::Easy,
+                        "medium" => Difficulty::Medium,
+                        "hard" => Difficulty::Hard,
+                        _ => return Err(bad_value()),
+                    };
+                    let player = PLAYER_COLORS
+                        .iter()
+                        .position(|name| flag[2..] == **name)
+                        .unwrap();
+                    options.bots[player] = Some(difficulty);
+                }
+                _ => return Err(format!("unknown option {flag}")),
+            }
+        }
+        let GameSettings { max_x, max_y, .. } = options.settings;
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&players) {
+            return Err(format!("{players} players can't play"));
+        }
+        if max_x > 0 && max_y > 0 {
+            options.settings.layout = Layout::preset(preset, max_x, max_y, players);
+        }
+        Ok(options)
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut game = match CrossesCore::new(options.settings) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut bot = Bot::default();
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        if let GameState::Ended(game_over) = game.player_manager.game_state() {
+            print_board(&game);
+            println!("{game_over}");
+            return ExitCode::SUCCESS;
+        }
+        let player = game.player_manager.current_player();
+        // Bots only play at the end of the log, so that undo can be used to look back.
+        if let (Some(difficulty), false) = (options.bots[player], game.can_forward()) {
+            let before = game.log.len();
+            if let Err(e) = bot.play_turn(&mut game, difficulty) {
+                eprintln!("error: bot made an illegal move: {e}");
+                return ExitCode::FAILURE;
+            }
+            if game.log.len() == before {
+                eprintln!("error: {} bot has no moves", player_name(player));
+                return ExitCode::FAILURE;
+            }
+            let moves: Vec<_> = game.log[before..]
+                .iter()
+                .map(|&index| format_index(index))
+                .collect();
+            println!("{} bot: {}", player_name(player), moves.join(" "));
+            continue;
+        }
+
+        print_board(&game);
+        print!("{} > ", player_name(player));
+        io::stdout().flush().ok();
+        let Some(Ok(line)) = lines.next() else {
+            return ExitCode::SUCCESS;
+        };
+        match line.trim() {
+            "" => {}
+            "quit" | "exit" => return ExitCode::SUCCESS,
+            "help" => println!("{HELP}"),
+            "undo" => report(game.back()),
+            "redo" => report(game.forward()),
+            "export" => print!("{}", game.export()),
+            "variations" => {
+                let moves: Vec<_> = game.variations().into_iter().map(format_index).collect();
+                println!("{}", moves.join(" "));
+            }
+            "promote" => game.promote_variation(),
+            "moves" => match game.notation() {
+                Ok(notation) => println!("{notation}"),
+                Err(e) => println!("error: {e}"),
+            },
+            command if command.starts_with("line ") => match parse_move(command[5..].trim()) {
+                Some(index) => report(game.choose_variation(index)),
+                None => println!("error: \"{}\" is not a move", command[5..].trim()),
+            },
+            command => match parse_move(command) {
+                Some((x, y)) => report(game.make_move(x, y)),
+                None => println!("error: unknown command \"{command}\", try \"help\""),
+            },
+        }
+    }
+}
+
+fn print_board(game: &CrossesCore) {
+    let board = &game.board_manager;
+    let width = column_name(board.max_x - 1).len() + 1;
+    print!("    ");
+    for x in 0..board.max_x {
+        print!("{:<width$}", column_name(x));
+    }
+    println!();
+    for y in 0..board.max_y {
+        print!("{:>3} ", y + 1);
+        for x in 0..board.max_x {
+            print!("{:<width$}", cell_to_char(board.get((x, y))));
+        }
+        println!();
+    }
+    let counters: Vec<_> = (0..board.players())
+        .map(|player| {
+            format!(
+                "{} ({}, {})",
+                player_name(player),
+                board.moves_counter[player],
+                board.crosses_counter[player]
+            )
+        })
+        .collect();
+    println!("moves and crosses: {}", counters.join(", "));
+}
+
+/// Accepts both the move notation (`c3`, `c3*`) and raw coordinates (`2,2`).
+fn parse_move(text: &str) -> Option<(usize, usize)> {
+    if let Some((x, y)) = text.split_once(',') {
+        return Some((x.trim().parse().ok()?, y.trim().parse().ok()?));
+    }
+    parse_index(text.strip_suffix('*').unwrap_or(text))
+}
+
+fn report<E: std::fmt::Display>(result: Result<(), E>) {
+    if let Err(e) = result {
+        println!("error: {e}");
+    }
+}
+
+fn player_name(player: usize) -> &'static str {
+    PLAYER_COLORS[player]
+}
@@ -1,5 +1,6 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
-mod sample_core;
+pub mod network;
+pub mod sample_core;
 pub use app::TemplateApp;
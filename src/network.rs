@@ -0,0 +1,831 @@
+//! Playing over TCP. One instance hosts the game and keeps the only
+//! authoritative `CrossesCore`, the others join it by address.
+//!
+//! The protocol is line based, one message per line:
+//!
+//! * guest → host: `hello VERSION` once after connecting, or `hello VERSION
+//!   watch` to only watch, then `move x,y` to ask for a move and `sync` to ask
+//!   for the whole game;
+//! * host → guest: `welcome VERSION SEAT`, where `SEAT` is the player the
+//!   guest plays for or `-` if it only watches; `move x,y` for every move made
+//!   on the host, whoever made it; `clock MS... PLAYER@MOVES...` after every
+//!   move of a timed game and whenever someone's time runs out, with the time
+//!   each player has left and the players knocked out by the clock;
+//!   `rejected ERROR` when the guest's move was refused; `game N` followed by
+//!   `N` lines of the save format; `bye REASON` right before the host closes
+//!   the connection.
+//!
+//! A line longer than `MAX_LINE_LEN` bytes closes the connection.
+//!
+//! Moves appended to the end of the log are the only thing sent as they
+//! happen. Anything else that changes the game on the host (undo, a new game,
+//! an import) is sent as the whole game, and so is the game a guest gets after
+//! joining or reconnecting, with the whole log.
+//!
+//! Guests run the clock on their own between moves, but the time the host sends
+//! replaces theirs, and only the host knocks out a player whose time ran out.
+//!
+//! A guest that only watches may look back over the game: moves keep being
+//! added to the end of its log while its cursor stays where it was.
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::sample_core::{save_format::ImportError, CrossesCore, GameSettings};
+
+pub const PROTOCOL_VERSION: u32 = 4;
+pub const DEFAULT_PORT: u16 = 7878;
+/// A saved game is a few lines plus a line per row of the board.
+const MAX_GAME_LINES: usize = 256;
+/// The longest line is the notation of a game's tree, a few bytes per move.
+pub const MAX_LINE_LEN: usize = 1 << 20;
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Called from network threads when something arrives, e.g. to request a repaint.
+pub type Waker = Arc<dyn Fn() + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Hello {
+        version: u32,
+        watch: bool,
+    },
+    Welcome {
+        version: u32,
+        seat: Option<usize>,
+    },
+    Move((usize, usize)),
+    Clock {
+        remaining: Vec<Duration>,
+        flags: Vec<(usize, usize)>,
+    },
+    Rejected(String),
+    Sync,
+    Game(String),
+    Bye(String),
+}
+impl Message {
+    pub fn encode(&self) -> String {
+        // Free text has to stay on its line.
+        let line = |text: &str| text.replace(['\r', '\n'], " ");
+        match self {
+            Message::Hello {
+                version,
+                watch: false,
+            } => format!("hello {version}\n"),
+            Message::Hello {
+                version,
+                watch: true,
+            } => format!("hello {version} watch\n"),
+            Message::Welcome { version, seat } => match seat {
+                Some(seat) => format!("welcome {version} {seat}\n"),
+                None => format!("welcome {version} -\n"),
+            },
+            Message::Move((x, y)) => format!("move {x},{y}\n"),
+            Message::Clock { remaining, flags } => {
+                let mut line = "clock".to_string();
+                for remaining in remaining {
+                    line += &format!(" {}", remaining.as_millis());
+                }
+                for (player, moves) in flags {
+                    line += &format!(" {player}@{moves}");
+                }
+                line + "\n"
+            }
+            Message::Rejected(error) => format!("rejected {}\n", line(error)),
+            Message::Sync => "sync\n".to_string(),
+            Message::Game(text) => {
+                let lines: Vec<_> = text.lines().collect();
+                format!("game {}\n{}\n", lines.len(), lines.join("\n"))
+            }
+            Message::Bye(reason) => format!("bye {}\n", line(reason)),
+        }
+    }
+    /// The next message, or `None` once the other side has closed the connection.
+    pub fn read(reader: &mut impl BufRead) -> Result<Option<Self>, NetworkError> {
+        let Some(line) = read_line(reader)? else {
+            return Ok(None);
+        };
+        let malformed = || NetworkError::Malformed(line.clone());
+        let (keyword, rest) = line.split_once(' ').unwrap_or((&line, ""));
+        let message = match keyword {
+            "hello" => {
+                let (version, watch) = match rest.split_once(' ') {
+                    Some((version, "watch")) => (version, true),
+                    Some(_) => return Err(malformed()),
+                    None => (rest, false),
+                };
+                Message::Hello {
+                    version: version.parse().map_err(|_| malformed())?,
+                    watch,
+                }
+            }
+            "welcome" => {
+                let (version, seat) = rest.split_once(' ').ok_or_else(malformed)?;
+                Message::Welcome {
+                    version: version.parse().map_err(|_| malformed())?,
+                    seat: match seat {
+                        "-" => None,
+                        seat => Some(seat.parse().map_err(|_| malformed())?),
+                    },
+                }
+            }
+            "move" => {
+                let (x, y) = rest.split_once(',').ok_or_else(malformed)?;
+                Message::Move((
+                    x.parse().map_err(|_| malformed())?,
+                    y.parse().map_err(|_| malformed())?,
+                ))
+            }
+            "clock" => {
+                let mut remaining = Vec::new();
+                let mut flags = Vec::new();
+                for token in rest.split_whitespace() {
+                    match token.split_once('@') {
+                        Some((player, moves)) => flags.push((
+                            player.parse().map_err(|_| malformed())?,
+                            moves.parse().map_err(|_| malformed())?,
+                        )),
+                        None => remaining.push(Duration::from_millis(
+                            token.parse().map_err(|_| malformed())?,
+                        )),
+                    }
+                }
+                Message::Clock { remaining, flags }
+            }
+            "rejected" => Message::Rejected(rest.to_string()),
+            "sync" if rest.is_empty() => Message::Sync,
+            "game" => {
+                let count: usize = rest.parse().map_err(|_| malformed())?;
+                if count > MAX_GAME_LINES {
+                    return Err(malformed());
+                }
+                let mut text = String::new();
+                for _ in 0..count {
+                    text += &read_line(reader)?.ok_or_else(malformed)?;
+                    text.push('\n');
+                }
+                Message::Game(text)
+            }
+            "bye" => Message::Bye(rest.to_string()),
+            _ => return Err(malformed()),
+        };
+        Ok(Some(message))
+    }
+}
+
+/// A line that doesn't end within `MAX_LINE_LEN` bytes is an error, so that
+/// a peer can't make us buffer without end.
+fn read_line(reader: &mut impl BufRead) -> Result<Option<String>, NetworkError> {
+    let mut line = String::new();
+    let read = Read::take(reader, MAX_LINE_LEN as u64).read_line(&mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read == MAX_LINE_LEN && !line.ends_with('\n') {
+        return Err(NetworkError::TooLong);
+    }
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+fn send(mut stream: &TcpStream, message: &Message) -> io::Result<()> {
+    stream.write_all(message.encode().as_bytes())
+}
+
+/// Something that happened to the connection, for the user to see.
+#[derive(Debug)]
+pub enum Notice {
+    /// A guest joined the host.
+    Joined {
+        address: SocketAddr,
+        seat: Option<usize>,
+    },
+    /// A guest left the host.
+    Left {
+        address: SocketAddr,
+        reason: String,
+    },
+    /// The guest got into the host's game.
+    Connected {
+        seat: Option<usize>,
+    },
+    /// The guest lost the host and keeps trying to get back.
+    Disconnected(String),
+    /// The host refused the guest's move.
+    Rejected(String),
+    /// The guest's game went out of sync with the host and was asked for again.
+    Resync,
+    Error(NetworkError),
+}
+impl Display for Notice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Notice::Joined {
+                address,
+                seat: Some(seat),
+            } => write!(f, "{address} joined as player {seat}"),
+            Notice::Joined {
+                address,
+                seat: None,
+            } => write!(f, "{address} joined to watch"),
+            Notice::Left { address, reason } => write!(f, "{address} left: {reason}"),
+            Notice::Connected { seat: Some(seat) } => {
+                write!(f, "connected, playing as player {seat}")
+            }
+            Notice::Connected { seat: None } => write!(f, "connected, watching"),
+            Notice::Disconnected(reason) => write!(f, "disconnected: {reason}"),
+            Notice::Rejected(error) => write!(f, "move rejected: {error}"),
+            Notice::Resync => write!(f, "game went out of sync, asked the host for it"),
+            Notice::Error(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NetworkError {
+    Io(io::Error),
+    Malformed(String),
+    TooLong,
+    Version(u32),
+    NotConnected,
+    Import(ImportError),
+}
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::Io(e) => write!(f, "{e}"),
+            NetworkError::Malformed(line) => write!(f, "malformed message \"{line}\""),
+            NetworkError::TooLong => write!(f, "message line is over {MAX_LINE_LEN} bytes"),
+            NetworkError::Version(version) => write!(
+                f,
+                "protocol version {version} doesn't match ours ({PROTOCOL_VERSION})"
+            ),
+            NetworkError::NotConnected => write!(f, "not connected"),
+            NetworkError::Import(e) => write!(f, "can't load the host's game: {e}"),
+        }
+    }
+}
+impl Error for NetworkError {}
+impl From<io::Error> for NetworkError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+impl From<ImportError> for NetworkError {
+    fn from(value: ImportError) -> Self {
+        Self::Import(value)
+    }
+}
+
+/// Reads messages until the connection closes, then reports why.
+fn read_messages<T: Send + 'static>(
+    stream: TcpStream,
+    events: Sender<T>,
+    wake: Waker,
+    received: impl Fn(Message) -> T,
+    closed: impl Fn(String) -> T,
+) {
+    let mut reader = BufReader::new(stream);
+    let reason = loop {
+        match Message::read(&mut reader) {
+            Ok(Some(message)) => {
+                if events.send(received(message)).is_err() {
+                    return;
+                }
+            }
+            Ok(None) => break "connection closed".to_string(),
+            Err(e) => break e.to_string(),
+        }
+        wake();
+    };
+    events.send(closed(reason)).ok();
+    wake();
+}
+
+enum HostEvent {
+    Connected(usize, TcpStream, SocketAddr),
+    Received(usize, Message),
+    Closed(usize, String),
+}
+
+struct Peer {
+    id: usize,
+    stream: TcpStream,
+    address: SocketAddr,
+    /// `None` until the guest says hello, then the seat it got, if any.
+    seat: Option<Option<usize>>,
+}
+
+pub struct Host {
+    port: u16,
+    /// Players that guests play for, handed out in the order guests join.
+    seats: Vec<usize>,
+    peers: Vec<Peer>,
+    events: Receiver<HostEvent>,
+    stop: Arc<AtomicBool>,
+    /// Joined on drop, so that the port is free again once the host is gone.
+    accept: Option<JoinHandle<()>>,
+    published: Published,
+}
+/// The game as of the last `update`, to tell a new move from any other change.
+/// A game replaced by one with other settings is a change even if both logs are empty.
+#[derive(PartialEq)]
+struct Published {
+    settings: GameSettings,
+    log: Vec<(usize, usize)>,
+    cursor: usize,
+    flags: Vec<(usize, usize)>,
+}
+impl Published {
+    fn new(game: &CrossesCore) -> Self {
+        Self {
+            settings: game.settings.clone(),
+            log: game.log.clone(),
+            cursor: game.player_manager.current_move(),
+            flags: game.flags.clone(),
+        }
+    }
+    fn same_moves(&self, other: &Self) -> bool {
+        self.settings == other.settings && self.log == other.log && self.cursor == other.cursor
+    }
+}
+impl Host {
+    /// Listens on all interfaces, so that guests on localhost can join as well.
+    pub fn start(
+        port: u16,
+        seats: Vec<usize>,
+        game: &CrossesCore,
+        wake: Waker,
+    ) -> Result<Self, NetworkError> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let port = listener.local_addr()?.port();
+        listener.set_nonblocking(true)?;
+        let (sender, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let accept = thread::spawn(move || {
+            let mut next_id = 0;
+            while !stopped.load(Ordering::Relaxed) {
+                let (stream, address) = match listener.accept() {
+                    Ok(accepted) => accepted,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_INTERVAL);
+                        continue;
+                    }
+                    Err(e) => {
+                        log::warn!("can't accept a guest: {e}");
+                        continue;
+                    }
+                };
+                let id = next_id;
+                next_id += 1;
+                let reader = match prepare(&stream).and_then(|()| stream.try_clone()) {
+                    Ok(reader) => reader,
+                    Err(e) => {
+                        log::warn!("can't talk to {address}: {e}");
+                        continue;
+                    }
+                };
+                if sender
+                    .send(HostEvent::Connected(id, stream, address))
+                    .is_err()
+                {
+                    break;
+                }
+                let (sender, wake) = (sender.clone(), wake.clone());
+                thread::spawn(move || {
+                    read_messages(
+                        reader,
+                        sender,
+                        wake,
+                        |message| HostEvent::Received(id, message),
+                        |reason| HostEvent::Closed(id, reason),
+                    )
+                });
+            }
+        });
+        Ok(Self {
+            port,
+            seats,
+            peers: Vec::new(),
+            events,
+            stop,
+            accept: Some(accept),
+            published: Published::new(game),
+        })
+    }
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+    /// Whether `player` is played by a guest, connected or not.
+    pub fn is_remote(&self, player: usize) -> bool {
+        self.seats.contains(&player)
+    }
+    /// Connected guests that said hello, and the seats they got.
+    pub fn guests(&self) -> Vec<(SocketAddr, Option<usize>)> {
+        self.peers
+            .iter()
+            .filter_map(|peer| Some((peer.address, peer.seat?)))
+            .collect()
+    }
+    /// Handles everything the guests sent, plays their moves on `game` and
+    /// sends the guests whatever changed in `game` since the last call.
+    pub fn update(&mut self, game: &mut CrossesCore) -> Vec<Notice> {
+        let mut notices = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                HostEvent::Connected(id, stream, address) => self.peers.push(Peer {
+                    id,
+                    stream,
+                    address,
+                    seat: None,
+                }),
+                HostEvent::Received(id, message) => {
+                    if let Some(notice) = self.receive(id, message, game) {
+                        notices.push(notice);
+                    }
+                }
+                HostEvent::Closed(id, reason) => {
+                    if let Some(notice) = self.drop_peer(id, reason) {
+                        notices.push(notice);
+                    }
+                }
+            }
+        }
+        let published = Published::new(game);
+        if published != self.published {
+            let old = &self.published;
+            let mut messages = Vec::new();
+            if !published.same_moves(old) {
+                let appended = old.settings == published.settings
+                    && old.cursor == old.log.len()
+                    && published.cursor == published.log.len();
+                messages.push(match published.log.split_last() {
+                    Some((&last, before)) if appended && before == old.log => Message::Move(last),
+                    _ => Message::Game(game.export()),
+                });
+            }
+            // The clock goes with every move and on its own when a flag falls.
+            // The whole game carries it anyway.
+            if let Some(clock) = &game.clock {
+                if !matches!(messages.last(), Some(Message::Game(_))) {
+                    messages.push(Message::Clock {
+                        remaining: clock.remaining.clone(),
+                        flags: game.flags.clone(),
+                    });
+                }
+            }
+            self.published = published;
+            for message in messages {
+                notices.extend(self.broadcast(&message));
+            }
+        }
+        notices
+    }
+    fn receive(&mut self, id: usize, message: Message, game: &mut CrossesCore) -> Option<Notice> {
+        let peer = self.peers.iter().position(|peer| peer.id == id)?;
+        let reply = match (self.peers[peer].seat, message) {
+            (
+                None,
+                Message::Hello {
+                    version: PROTOCOL_VERSION,
+                    watch,
+                },
+            ) => {
+                let taken: Vec<_> = self.peers.iter().filter_map(|peer| peer.seat?).collect();
+                let seat = self
+                    .seats
+                    .iter()
+                    .copied()
+                    .find(|seat| !taken.contains(seat))
+                    .filter(|_| !watch);
+                self.peers[peer].seat = Some(seat);
+                let welcome = Message::Welcome {
+                    version: PROTOCOL_VERSION,
+                    seat,
+                };
+                let stream = &self.peers[peer].stream;
+                return match send(stream, &welcome)
+                    .and_then(|()| send(stream, &Message::Game(game.export())))
+                {
+                    Ok(()) => Some(Notice::Joined {
+                        address: self.peers[peer].address,
+                        seat,
+                    }),
+                    Err(e) => self.drop_peer(id, e.to_string()),
+                };
+            }
+            (None, Message::Hello { version, .. }) => {
+                let error = NetworkError::Version(version).to_string();
+                send(&self.peers[peer].stream, &Message::Bye(error.clone())).ok();
+                return self.drop_peer(id, error);
+            }
+            (Some(None), Message::Move(_)) => {
+                Some(Message::Rejected("spectators can't move".to_string()))
+            }
+            (Some(seat), Message::Move((x, y))) => {
+                if seat != Some(game.player_manager.current_player()) {
+                    Some(Message::Rejected("it's not your turn".to_string()))
+                } else if game.can_forward() {
+                    // A move now would cut off the moves the host is looking back over.
+                    Some(Message::Rejected(
+                        "the host is reviewing the game".to_string(),
+                    ))
+                } else {
+                    // An accepted move reaches everyone, the sender too, in `update`.
+                    game.make_move(x, y)
+                        .err()
+                        .map(|e| Message::Rejected(e.to_string()))
+                }
+            }
+            (Some(_), Message::Sync) => Some(Message::Game(game.export())),
+            (_, message) => {
+                let error = format!("unexpected message \"{}\"", message.encode().trim_end());
+                send(&self.peers[peer].stream, &Message::Bye(error.clone())).ok();
+                return self.drop_peer(id, error);
+            }
+        };
+        let reply = reply?;
+        match send(&self.peers[peer].stream, &reply) {
+            Ok(()) => None,
+            Err(e) => self.drop_peer(id, e.to_string()),
+        }
+    }
+    fn broadcast(&mut self, message: &Message) -> Vec<Notice> {
+        let failed: Vec<_> = self
+            .peers
+            .iter()
+            .filter(|peer| peer.seat.is_some())
+            .filter_map(|peer| Some((peer.id, send(&peer.stream, message).err()?)))
+            .collect();
+        failed
+            .into_iter()
+            .filter_map(|(id, e)| self.drop_peer(id, e.to_string()))
+            .collect()
+    }
+    fn drop_peer(&mut self, id: usize, reason: String) -> Option<Notice> {
+        let peer = self.peers.iter().position(|peer| peer.id == id)?;
+        let peer = self.peers.remove(peer);
+        peer.stream.shutdown(Shutdown::Both).ok();
+        // Guests that never said hello aren't worth mentioning.
+        peer.seat.map(|_| Notice::Left {
+            address: peer.address,
+            reason,
+        })
+    }
+}
+impl Drop for Host {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for peer in &self.peers {
+            send(
+                &peer.stream,
+                &Message::Bye("host closed the game".to_string()),
+            )
+            .ok();
+            peer.stream.shutdown(Shutdown::Both).ok();
+        }
+        if let Some(accept) = self.accept.take() {
+            accept.join().ok();
+        }
+    }
+}
+
+fn prepare(stream: &TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))
+}
+
+enum GuestEvent {
+    Connected(TcpStream),
+    Failed(String),
+    Received(Message),
+    Closed(String),
+}
+
+pub struct Guest {
+    address: String,
+    /// Asked to only watch, even if the host has a free seat.
+    watch: bool,
+    stream: Option<TcpStream>,
+    events: Receiver<GuestEvent>,
+    stop: Arc<AtomicBool>,
+    /// `None` until the host welcomes us, then the seat we got, if any.
+    seat: Option<Option<usize>>,
+    /// The last connection error, to report it only once while retrying.
+    failure: Option<String>,
+    /// Why the host said it was closing the connection.
+    farewell: Option<String>,
+    /// Where the host's cursor is, once we got its game. A spectator whose
+    /// cursor is elsewhere is looking back and is left there.
+    live: Option<usize>,
+}
+impl Guest {
+    /// Connects in the background and reconnects whenever the connection drops.
+    pub fn connect(address: String, watch: bool, wake: Waker) -> Self {
+        let (sender, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let target = address.clone();
+        thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                match open(&target) {
+                    Ok(stream) => {
+                        let Ok(reader) = stream.try_clone() else {
+                            continue;
+                        };
+                        if sender.send(GuestEvent::Connected(stream)).is_err() {
+                            return;
+                        }
+                        wake();
+                        read_messages(
+                            reader,
+                            sender.clone(),
+                            wake.clone(),
+                            GuestEvent::Received,
+                            GuestEvent::Closed,
+                        );
+                    }
+                    Err(e) => {
+                        if sender.send(GuestEvent::Failed(e.to_string())).is_err() {
+                            return;
+                        }
+                        wake();
+                    }
+                }
+                thread::sleep(RECONNECT_INTERVAL);
+            }
+        });
+        Self {
+            address,
+            watch,
+            stream: None,
+            events,
+            stop,
+            seat: None,
+            failure: None,
+            farewell: None,
+            live: None,
+        }
+    }
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+    /// The player we play for, once the host has welcomed us.
+    pub fn seat(&self) -> Option<usize> {
+        self.seat.flatten()
+    }
+    pub fn is_connected(&self) -> bool {
+        self.seat.is_some()
+    }
+    /// How many moves of the log the host has made, once we got its game.
+    pub fn live(&self) -> Option<usize> {
+        self.live
+    }
+    /// Asks the host for a move. It is made on `game` only when the host sends it back.
+    pub fn send_move(&mut self, index: (usize, usize)) -> Result<(), NetworkError> {
+        let stream = self
+            .stream
+            .as_ref()
+            .filter(|_| self.is_connected())
+            .ok_or(NetworkError::NotConnected)?;
+        send(stream, &Message::Move(index))?;
+        Ok(())
+    }
+    /// Applies whatever the host sent to `game`.
+    pub fn update(&mut self, game: &mut CrossesCore) -> Vec<Notice> {
+        let mut notices = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                GuestEvent::Connected(stream) => {
+                    self.failure = None;
+                    let hello = Message::Hello {
+                        version: PROTOCOL_VERSION,
+                        watch: self.watch,
+                    };
+                    if let Err(e) = send(&stream, &hello) {
+                        notices.push(Notice::Error(e.into()));
+                    }
+                    self.stream = Some(stream);
+                }
+                GuestEvent::Failed(reason) => {
+                    if self.failure.as_ref() != Some(&reason) {
+                        notices.push(Notice::Disconnected(reason.clone()));
+                        self.failure = Some(reason);
+                    }
+                }
+                GuestEvent::Closed(reason) => {
+                    self.stream = None;
+                    self.seat = None;
+                    notices.push(Notice::Disconnected(self.farewell.take().unwrap_or(reason)));
+                }
+                GuestEvent::Received(message) => {
+                    if let Some(notice) = self.receive(message, game) {
+                        notices.push(notice);
+                    }
+                }
+            }
+        }
+        notices
+    }
+    fn receive(&mut self, message: Message, game: &mut CrossesCore) -> Option<Notice> {
+        match message {
+            Message::Welcome { version, .. } if version != PROTOCOL_VERSION => {
+                Some(Notice::Error(NetworkError::Version(version)))
+            }
+            Message::Welcome { seat, .. } => {
+                self.seat = Some(seat);
+                Some(Notice::Connected { seat })
+            }
+            Message::Game(text) => match CrossesCore::import(&text) {
+                Ok(imported) => {
+                    let cursor = game.player_manager.current_move();
+                    let looking_back = self.live.is_some_and(|live| live != cursor);
+                    self.live = Some(imported.player_manager.current_move());
+                    *game = imported;
+                    if looking_back && cursor <= game.log.len() {
+                        game.seek(cursor).ok();
+                    }
+                    None
+                }
+                Err(e) => Some(Notice::Error(e.into())),
+            },
+            Message::Move((x, y)) => {
+                let Some(live) = self.live else {
+                    return Some(self.resync());
+                };
+                let cursor = game.player_manager.current_move();
+                let made = if cursor == live {
+                    game.make_move(x, y)
+                } else {
+                    // The move goes to the end of the log, the cursor comes back.
+                    game.seek(live)
+                        .and_then(|()| game.make_move(x, y))
+                        .and_then(|()| game.seek(cursor))
+                };
+                match made {
+                    Ok(()) => {
+                        self.live = Some(live + 1);
+                        None
+                    }
+                    Err(_) => Some(self.resync()),
+                }
+            }
+            Message::Clock { remaining, flags } => match game.sync_clock(remaining, flags) {
+                Ok(()) => None,
+                Err(_) => Some(self.resync()),
+            },
+            Message::Rejected(error) => Some(Notice::Rejected(error)),
+            Message::Bye(reason) => {
+                self.farewell = Some(reason);
+                None
+            }
+            message @ (Message::Hello { .. } | Message::Sync) => Some(Notice::Error(
+                NetworkError::Malformed(message.encode().trim_end().to_string()),
+            )),
+        }
+    }
+    fn resync(&mut self) -> Notice {
+        match self
+            .stream
+            .as_ref()
+            .map(|stream| send(stream, &Message::Sync))
+        {
+            Some(Ok(())) => Notice::Resync,
+            Some(Err(e)) => Notice::Error(e.into()),
+            None => Notice::Error(NetworkError::NotConnected),
+        }
+    }
+}
+impl Drop for Guest {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(stream) = &self.stream {
+            stream.shutdown(Shutdown::Both).ok();
+        }
+    }
+}
+
+fn open(address: &str) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, RECONNECT_INTERVAL) {
+            Ok(stream) => {
+                prepare(&stream)?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address resolves to nothing")))
+}
@@ -1,49 +1,306 @@
 use std::error::Error;
 use std::fmt::Display;
+use std::time::Duration;
 
 use crosses_core::board_manager::{BoardError, BoardManager, Cell, CellKind};
 use crosses_core::player_manager::{self, GameOver, GameState, LoseData};
 use serde::{Deserialize, Serialize};
 
+pub mod bot;
+pub mod clock;
+pub mod evaluation;
+pub mod game_tree;
+pub mod layout;
+pub mod move_notation;
+pub mod preview;
 pub mod sample_board_manager;
 pub mod sample_cell;
+pub mod save_format;
+pub mod validation;
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameSettings {
+    pub max_x: usize,
+    pub max_y: usize,
+    pub moves_per_turn: usize,
+    pub layout: layout::Layout,
+    /// Без контроля времени партия не ограничена по времени.
+    #[serde(default)]
+    pub time_control: Option<clock::TimeControl>,
+}
+impl GameSettings {
+    pub fn players(&self) -> usize {
+        self.layout.players()
+    }
+}
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            max_x: 10,
+            max_y: 10,
+            moves_per_turn: 4,
+            layout: Default::default(),
+            time_control: None,
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct CrossesCore {
     pub board_manager: sample_board_manager::SampleBoardManager,
-    pub player_manager: player_manager::PlayerManager<[Option<LoseData>; 2]>,
+    pub player_manager: player_manager::PlayerManager<Vec<Option<LoseData>>>,
+    /// Текущая линия дерева вариантов, включая отменённые ходы.
     pub log: Vec<(usize, usize)>,
+    /// Появилось позже остальных полей, старые сохранения его не содержат.
+    #[serde(default)]
+    pub tree: game_tree::GameTree,
+    /// Есть только в партиях с контролем времени.
+    #[serde(default)]
+    pub clock: Option<clock::Clock>,
+    /// Кто выбыл по времени и сколько ходов партии было сделано к тому моменту.
+    /// `PlayerManager` не умеет отменять выбывание, поэтому через такие позиции
+    /// ходы отматываются пересборкой с начала, см. `rewind`.
+    #[serde(default)]
+    pub flags: Vec<(usize, usize)>,
+    pub settings: GameSettings,
 }
 impl CrossesCore {
+    pub fn new(settings: GameSettings) -> Result<Self, CrossesError> {
+        if settings.moves_per_turn == 0 {
+            return Err(sample_board_manager::SetupError::NoMovesPerTurn.into());
+        }
+        Ok(Self {
+            board_manager: sample_board_manager::SampleBoardManager::new(
+                settings.max_x,
+                settings.max_y,
+                &settings.layout,
+            )?,
+            player_manager: player_manager::PlayerManager::new(
+                settings.moves_per_turn,
+                settings.players(),
+                vec![None; settings.players()],
+            ),
+            log: Vec::new(),
+            tree: Default::default(),
+            clock: settings
+                .time_control
+                .map(|control| clock::Clock::new(control, settings.players())),
+            flags: Vec::new(),
+            settings,
+        })
+    }
+    /// Начальная позиция той же партии без часов, но с выбываниями по времени.
+    /// На ней проигрываются записи ходов.
+    fn start_position(&self) -> Result<Self, CrossesError> {
+        let mut start = Self::new(GameSettings {
+            time_control: None,
+            ..self.settings.clone()
+        })?;
+        start.flags = self.flags.clone();
+        start.resign_flags();
+        Ok(start)
+    }
     pub fn make_move(&mut self, x: usize, y: usize) -> Result<(), CrossesError> {
         if let GameState::Ended(game_over) = self.player_manager.game_state() {
             return Err(CrossesError::PlayerError(game_over));
         }
-        self.board_manager
-            .make_move((x, y), self.player_manager.current_player() == 1)?;
+        // Часы показывают время конца лога. Ход посреди лога отрезал бы
+        // продолжение, за которое добавки уже начислены.
+        if self.clock.is_some() && self.can_forward() {
+            return Err(CrossesError::NotAtEnd);
+        }
+        let current_move = self.player_manager.current_move();
+        let player = self.player_manager.current_player();
+        self.board_manager.make_move((x, y), player)?;
         self.player_manager.advance(
             |p| self.board_manager.moves_counter[p] == 0,
             |p| self.board_manager.crosses_counter[p] == 0,
         );
-        self.log.truncate(self.player_manager.current_move() - 1);
-        self.log.push((x, y));
+        self.resign_flags();
+        // Добавка за переход хода. Ходы с часами делаются только в конце лога,
+        // так что за каждый переход хода она начисляется один раз.
+        if let (Some(clock), Some(control)) = (&mut self.clock, self.settings.time_control) {
+            if self.player_manager.current_player() != player {
+                clock.add(player, control.increment);
+            }
+        }
+        // Ход, уже записанный в линии, просто повторяется. Другой ход уводит
+        // в вариант, а прежнее продолжение остаётся в дереве.
+        if self.log.get(current_move) != Some(&(x, y)) {
+            self.log.truncate(current_move);
+            self.log.push((x, y));
+            let node = self.tree.insert(&self.log);
+            self.log.extend(self.tree.main_line(node));
+        }
+        Ok(())
+    }
+    /// Ходы, которыми в дереве продолжалась текущая позиция. Первый — главное продолжение.
+    pub fn variations(&self) -> Vec<(usize, usize)> {
+        let current_move = self.player_manager.current_move();
+        match self.tree.find(&self.log[..current_move]) {
+            Some(node) => self
+                .tree
+                .children(node)
+                .iter()
+                .map(|&child| self.tree.index(child))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+    /// Переключает линию после текущей позиции на вариант, начинающийся ходом `index`.
+    /// Сама позиция не меняется.
+    pub fn choose_variation(&mut self, index: (usize, usize)) -> Result<(), CrossesError> {
+        let current_move = self.player_manager.current_move();
+        let node = self
+            .tree
+            .find(&self.log[..current_move])
+            .and_then(|node| self.tree.child(node, index))
+            .ok_or(CrossesError::UnknownVariation)?;
+        self.log.truncate(current_move);
+        self.log.push(index);
+        self.log.extend(self.tree.main_line(node));
+        Ok(())
+    }
+    pub fn is_main_line(&self) -> bool {
+        self.tree.main_line(game_tree::ROOT) == self.log
+    }
+    /// Делает текущую линию главной.
+    pub fn promote_variation(&mut self) {
+        self.tree.promote(&self.log);
+    }
+    /// Сколько ходов текущий игрок уже сделал в этом переходе хода.
+    pub fn moves_made_in_turn(&self) -> usize {
+        self.player_manager.current_sub_move()
+    }
+    /// Списывает `elapsed` с часов текущего игрока. Часы стоят, пока игра окончена.
+    /// Посреди лога идущая партия не бывает: назад её не отмотать, см. `ensure_reviewable`.
+    /// Игрок, у которого кончилось время, выбывает, а ход переходит к следующему.
+    pub fn tick(&mut self, elapsed: Duration) {
+        if let Some(player) = self.spend_time(elapsed) {
+            self.flags
+                .push((player, self.player_manager.current_move()));
+            self.player_manager.resign(player);
+        }
+    }
+    /// Как `tick`, но игрок, у которого кончилось время, не выбывает, а только
+    /// возвращается. Так часы идут у гостя: выбывания ему присылает хост.
+    pub fn spend_time(&mut self, elapsed: Duration) -> Option<usize> {
+        if self.is_over() || self.can_forward() {
+            return None;
+        }
+        let player = self.player_manager.current_player();
+        let clock = self.clock.as_mut()?;
+        clock.spend(player, elapsed);
+        clock.remaining[player].is_zero().then_some(player)
+    }
+    /// Выводит из игры тех, у кого кончилось время при нынешнем числе сделанных ходов.
+    fn resign_flags(&mut self) {
+        let made = self.player_manager.current_move();
+        for &(player, at) in &self.flags {
+            if at == made {
+                self.player_manager.resign(player);
+            }
+        }
+    }
+    /// Принимает время и выбывания по времени с другой копии партии, например
+    /// с хоста. Если выбывания изменились, позиция собирается заново.
+    pub fn sync_clock(
+        &mut self,
+        remaining: Vec<Duration>,
+        flags: Vec<(usize, usize)>,
+    ) -> Result<(), CrossesError> {
+        let players = self.settings.players();
+        let fits = remaining.len() == players
+            && flags
+                .iter()
+                .all(|&(player, at)| player < players && at <= self.log.len());
+        let clock = match &mut self.clock {
+            Some(clock) if fits => clock,
+            _ => return Err(CrossesError::ClockMismatch),
+        };
+        clock.remaining = remaining;
+        if flags != self.flags {
+            self.flags = flags;
+            self.rewind(self.player_manager.current_move())?;
+        }
+        Ok(())
+    }
+    pub fn is_over(&self) -> bool {
+        matches!(self.player_manager.game_state(), GameState::Ended(_))
+    }
+    /// Окончена ли игра в конце лога, где бы ни стоял курсор.
+    pub fn is_finished(&self) -> bool {
+        if !self.can_forward() {
+            return self.is_over();
+        }
+        let Ok(mut end) = self.start_position() else {
+            return false;
+        };
+        self.log.iter().all(|&(x, y)| end.make_move(x, y).is_ok()) && end.is_over()
+    }
+    /// Партию с часами просматривают только после её конца: пока часы идут,
+    /// они идут для позиции в конце лога.
+    fn ensure_reviewable(&self) -> Result<(), CrossesError> {
+        if self.clock.is_some() && !self.is_finished() {
+            return Err(CrossesError::ClockRunning);
+        }
         Ok(())
     }
+    /// Почему игрок выбыл: по времени или по тому, что записал `PlayerManager`.
+    pub fn lose_reason(&self, player: usize) -> Option<LoseReason> {
+        if self.flags.iter().any(|&(flagged, _)| flagged == player) {
+            return Some(LoseReason::OutOfTime);
+        }
+        match self
+            .player_manager
+            .lose_data()
+            .get(player)
+            .copied()
+            .flatten()?
+        {
+            LoseData::OutOfMoves => Some(LoseReason::OutOfMoves),
+            LoseData::OutOfCrosses => Some(LoseReason::OutOfCrosses),
+        }
+    }
+    /// Ходы текущего игрока. Пусто, если игра окончена.
+    pub fn legal_moves(&self) -> Vec<sample_board_manager::LegalMove> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        self.board_manager
+            .legal_moves(self.player_manager.current_player())
+    }
     pub fn can_back(&self) -> bool {
         self.player_manager.current_move() != 0
     }
     pub fn back(&mut self) -> Result<(), CrossesError> {
-        let index = *self
-            .log
-            .get(self.player_manager.current_move() - 1)
+        self.ensure_reviewable()?;
+        self.step_back()
+    }
+    fn step_back(&mut self) -> Result<(), CrossesError> {
+        if !self.flags.is_empty() {
+            let target = self
+                .player_manager
+                .current_move()
+                .checked_sub(1)
+                .ok_or(CrossesError::BackError)?;
+            return self.rewind(target);
+        }
+        let index = self
+            .player_manager
+            .current_move()
+            .checked_sub(1)
+            .and_then(|last| self.log.get(last))
+            .copied()
             .ok_or(CrossesError::BackError)?;
         let cell = self.board_manager.get(index);
         if let CellKind::Empty | CellKind::Border = cell.kind() {
             return Err(CrossesError::CorruptedLog);
         }
         let player = cell.player();
-        self.board_manager.cancel_move(index, || !player)?;
-        self.player_manager.reverse(player as usize);
+        let origin = cell.origin();
+        self.board_manager.cancel_move(index, || origin)?;
+        self.player_manager.reverse(player);
         Ok(())
     }
     pub fn can_forward(&self) -> bool {
@@ -52,34 +309,110 @@ impl CrossesCore {
     pub fn forward(&mut self) -> Result<(), CrossesError> {
         let index = *self
             .log
-            .get(self.player_manager.current_move() - 1)
+            .get(self.player_manager.current_move())
             .ok_or(CrossesError::ForwardError)?;
         self.board_manager
-            .make_move(index, self.player_manager.current_player() == 1)?;
+            .make_move(index, self.player_manager.current_player())?;
         self.player_manager.advance(
             |p| self.board_manager.moves_counter[p] == 0,
             |p| self.board_manager.crosses_counter[p] == 0,
         );
+        self.resign_flags();
+        Ok(())
+    }
+    /// Последний сделанный ход, если он есть.
+    pub fn last_move(&self) -> Option<(usize, usize)> {
+        let last = self.player_manager.current_move().checked_sub(1)?;
+        self.log.get(last).copied()
+    }
+    /// Отменяет или возвращает ходы, пока сделанными не останутся первые `target` ходов лога.
+    pub fn seek(&mut self, target: usize) -> Result<(), CrossesError> {
+        if target > self.log.len() {
+            return Err(CrossesError::ForwardError);
+        }
+        if target < self.player_manager.current_move() {
+            self.ensure_reviewable()?;
+            if !self.flags.is_empty() {
+                return self.rewind(target);
+            }
+        }
+        while self.player_manager.current_move() > target {
+            self.step_back()?;
+        }
+        while self.player_manager.current_move() < target {
+            self.forward()?;
+        }
+        Ok(())
+    }
+    /// Собирает позицию из первых `target` ходов лога заново.
+    fn rewind(&mut self, target: usize) -> Result<(), CrossesError> {
+        let start = self.start_position()?;
+        self.board_manager = start.board_manager;
+        self.player_manager = start.player_manager;
+        while self.player_manager.current_move() < target {
+            self.forward()?;
+        }
         Ok(())
     }
 }
 
+#[cfg(test)]
+impl CrossesCore {
+    /// Делает `moves` первых допустимых ходов, заготовка для тестов.
+    pub(crate) fn play_first_moves(&mut self, moves: usize) {
+        for _ in 0..moves {
+            let (x, y) = self.legal_moves()[0].index;
+            self.make_move(x, y).unwrap();
+        }
+    }
+}
+
+impl Default for CrossesCore {
+    fn default() -> Self {
+        Self::new(GameSettings::default()).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoseReason {
+    OutOfMoves,
+    OutOfCrosses,
+    OutOfTime,
+}
+
 #[derive(Debug)]
 pub enum CrossesError {
     BoardError(BoardError),
     PlayerError(GameOver),
+    SetupError(sample_board_manager::SetupError),
     BackError,
     ForwardError,
     CorruptedLog,
+    UnknownVariation,
+    ClockMismatch,
+    ClockRunning,
+    NotAtEnd,
 }
 impl Display for CrossesError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CrossesError::BoardError(be) => write!(f, "{}", be),
             CrossesError::PlayerError(pe) => write!(f, "{}", pe),
+            CrossesError::SetupError(se) => write!(f, "{}", se),
             CrossesError::BackError => write!(f, "there's no going back"),
             CrossesError::ForwardError => write!(f, "nothing ahead"),
             CrossesError::CorruptedLog => write!(f, "log was corrupterd"),
+            CrossesError::UnknownVariation => write!(f, "no such variation here"),
+            CrossesError::ClockMismatch => write!(f, "clock doesn't match the game"),
+            CrossesError::ClockRunning => {
+                write!(f, "a timed game can't be looked back over until it's over")
+            }
+            CrossesError::NotAtEnd => {
+                write!(
+                    f,
+                    "moves of a timed game are made only at the end of its log"
+                )
+            }
         }
     }
 }
@@ -94,3 +427,8 @@ impl From<GameOver> for CrossesError {
         Self::PlayerError(value)
     }
 }
+impl From<sample_board_manager::SetupError> for CrossesError {
+    fn from(value: sample_board_manager::SetupError) -> Self {
+        Self::SetupError(value)
+    }
+}
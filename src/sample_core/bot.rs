@@ -0,0 +1,179 @@
+//! Компьютерный противник. Бот не знает правил сам по себе: ходы он берёт
+//! из `CrossesCore::legal_moves`, а последствия проверяет настоящим
+//! `SampleBoardManager::make_move` на копии доски.
+use std::cmp::Reverse;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crosses_core::board_manager::BoardManager;
+use crosses_core::player_manager::GameState;
+use serde::{Deserialize, Serialize};
+
+use super::{sample_board_manager::SampleBoardManager, CrossesCore, CrossesError};
+
+/// Сколько лучших ходов сложный бот проверяет на следующий за ними ход.
+const HARD_WIDTH: usize = 8;
+const WIN: i64 = 1_000_000;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// Случайный разрешённый ход.
+    Easy,
+    /// Лучший ход по оценке позиции сразу после него.
+    Medium,
+    /// Как `Medium`, но смотрит на ход дальше: на самый опасный ответ противника,
+    /// если ход переходит к нему, или на своё лучшее продолжение, если нет.
+    Hard,
+}
+impl Difficulty {
+    pub const ALL: [Self; 3] = [Self::Easy, Self::Medium, Self::Hard];
+}
+
+pub struct Bot {
+    rng: Rng,
+}
+impl Default for Bot {
+    fn default() -> Self {
+        Self { rng: Rng::new() }
+    }
+}
+impl Bot {
+    /// Выбирает ход за текущего игрока. `None`, если игра окончена или ходить некуда.
+    pub fn choose_move(
+        &mut self,
+        core: &CrossesCore,
+        difficulty: Difficulty,
+    ) -> Option<(usize, usize)> {
+        let player = core.player_manager.current_player();
+        let board = &core.board_manager;
+        let moves: Vec<_> = core.legal_moves().into_iter().map(|m| m.index).collect();
+        match difficulty {
+            Difficulty::Easy => {
+                if moves.is_empty() {
+                    None
+                } else {
+                    Some(moves[self.rng.below(moves.len())])
+                }
+            }
+            Difficulty::Medium => self.best(moves.into_iter().filter_map(|index| {
+                let after = after_move(board, index, player)?;
+                Some((evaluate(&after, player), index))
+            })),
+            Difficulty::Hard => {
+                let mut candidates: Vec<_> = moves
+                    .into_iter()
+                    .filter_map(|index| {
+                        let after = after_move(board, index, player)?;
+                        Some((evaluate(&after, player), index, after))
+                    })
+                    .collect();
+                candidates.sort_by_key(|&(score, ..)| Reverse(score));
+                candidates.truncate(HARD_WIDTH);
+                self.best(candidates.into_iter().map(|(score, index, after)| {
+                    // Посреди перехода хода следующим снова ходит сам бот.
+                    let mut players = core.player_manager.clone();
+                    players.advance(
+                        |p| after.moves_counter[p] == 0,
+                        |p| after.crosses_counter[p] == 0,
+                    );
+                    if let GameState::Ended(_) = players.game_state() {
+                        return (score, index);
+                    }
+                    let next = players.current_player();
+                    let replies = after
+                        .legal_moves(next)
+                        .into_iter()
+                        .filter_map(|reply| after_move(&after, reply.index, next))
+                        .map(|reply| evaluate(&reply, player));
+                    let reply = if next == player {
+                        replies.max()
+                    } else {
+                        replies.min()
+                    };
+                    (reply.unwrap_or(score), index)
+                }))
+            }
+        }
+    }
+    /// Ходит за текущего игрока, пока ход не перейдёт к другому или игра не закончится.
+    pub fn play_turn(
+        &mut self,
+        core: &mut CrossesCore,
+        difficulty: Difficulty,
+    ) -> Result<(), CrossesError> {
+        let player = core.player_manager.current_player();
+        while core.player_manager.current_player() == player {
+            let Some((x, y)) = self.choose_move(core, difficulty) else {
+                break;
+            };
+            core.make_move(x, y)?;
+        }
+        Ok(())
+    }
+    /// Ход с наибольшей оценкой, из равных выбирается случайный.
+    fn best(
+        &mut self,
+        scored: impl IntoIterator<Item = (i64, (usize, usize))>,
+    ) -> Option<(usize, usize)> {
+        let mut best = None;
+        let mut ties = 0;
+        for (score, index) in scored {
+            match best {
+                Some((best_score, _)) if score < best_score => {}
+                Some((best_score, _)) if score == best_score => {
+                    ties += 1;
+                    if self.rng.below(ties + 1) == 0 {
+                        best = Some((score, index));
+                    }
+                }
+                _ => {
+                    best = Some((score, index));
+                    ties = 0;
+                }
+            }
+        }
+        best.map(|(_, index)| index)
+    }
+}
+
+fn after_move(
+    board: &SampleBoardManager,
+    index: (usize, usize),
+    player: usize,
+) -> Option<SampleBoardManager> {
+    let mut board = board.clone();
+    board.make_move(index, player).ok()?;
+    Some(board)
+}
+
+/// Оценка позиции с точки зрения `player`: чем больше, тем лучше.
+/// Свои счётчики сравниваются со средними по противникам.
+fn evaluate(board: &SampleBoardManager, player: usize) -> i64 {
+    let lost = |p: usize| board.moves_counter[p] == 0 || board.crosses_counter[p] == 0;
+    let enemies = board.players() - 1;
+    if lost(player) {
+        return -WIN;
+    }
+    if (0..board.players()).filter(|&p| p != player).all(lost) {
+        return WIN;
+    }
+    let difference = |counter: &[usize]| {
+        let others: usize = counter.iter().sum::<usize>() - counter[player];
+        (counter[player] * enemies) as i64 - others as i64
+    };
+    difference(&board.moves_counter) + 4 * difference(&board.crosses_counter)
+}
+
+/// xorshift64*, чтобы не тянуть зависимость ради случайных ходов.
+struct Rng(u64);
+impl Rng {
+    fn new() -> Self {
+        Self(RandomState::new().build_hasher().finish() | 1)
+    }
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) % bound as u64) as usize
+    }
+}
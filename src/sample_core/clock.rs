@@ -0,0 +1,48 @@
+//! Шахматные часы: запас времени на партию и добавка за каждый переход хода.
+//!
+//! Переход хода — это серия ходов одного игрока, после которой `PlayerManager`
+//! передаёт ход следующему. Игрок, у которого кончилось время, выбывает из игры.
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeControl {
+    /// Время каждого игрока на всю партию.
+    pub total: Duration,
+    /// Сколько добавляется игроку, когда он передаёт ход.
+    pub increment: Duration,
+}
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            total: Duration::from_secs(5 * 60),
+            increment: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Оставшееся время игроков по порядку.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clock {
+    pub remaining: Vec<Duration>,
+}
+impl Clock {
+    pub fn new(control: TimeControl, players: usize) -> Self {
+        Self {
+            remaining: vec![control.total; players],
+        }
+    }
+    /// Списывает время с часов `player`, но не ниже нуля.
+    pub fn spend(&mut self, player: usize, elapsed: Duration) {
+        let remaining = &mut self.remaining[player];
+        *remaining = remaining.saturating_sub(elapsed);
+    }
+    /// Добавка не возвращает игрока, у которого время уже кончилось.
+    pub fn add(&mut self, player: usize, increment: Duration) {
+        let remaining = &mut self.remaining[player];
+        if !remaining.is_zero() {
+            *remaining += increment;
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Оценка позиции. Всё считается по тому, что уже лежит в клетках:
+//! активность показывает, куда дотягивается каждый игрок, а живость
+//! и важность — насколько прочно держатся его крестики и закрашенные клетки.
+use crosses_core::board_manager::{Cell, CellKind};
+
+use super::sample_board_manager::SampleBoardManager;
+
+const MOBILITY_WEIGHT: i64 = 1;
+const TERRITORY_WEIGHT: i64 = 2;
+const IMPORTANT_CROSS_WEIGHT: i64 = 3;
+const ALIVE_GROUP_WEIGHT: i64 = 5;
+const DEAD_GROUP_WEIGHT: i64 = -5;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    /// Сумма активности игрока по клеткам, в которые он может сходить.
+    pub mobility: usize,
+    /// Клетки, в которые может сходить только этот игрок.
+    pub territory: usize,
+    pub important_crosses: usize,
+    /// Связные группы закрашенных клеток, живые и мёртвые.
+    pub alive_groups: usize,
+    pub dead_groups: usize,
+}
+impl Score {
+    pub fn total(&self) -> i64 {
+        self.mobility as i64 * MOBILITY_WEIGHT
+            + self.territory as i64 * TERRITORY_WEIGHT
+            + self.important_crosses as i64 * IMPORTANT_CROSS_WEIGHT
+            + self.alive_groups as i64 * ALIVE_GROUP_WEIGHT
+            + self.dead_groups as i64 * DEAD_GROUP_WEIGHT
+    }
+}
+
+/// Кто сильнее всех дотягивается до клетки.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Heat {
+    pub player: usize,
+    /// Насколько его активность больше, чем у ближайшего соперника, от 1 до 3.
+    pub margin: u8,
+}
+
+pub struct Evaluation {
+    /// Оценка каждого игрока по порядку.
+    pub scores: Vec<Score>,
+    /// Построчно, как `SampleBoardManager::board`. `None` у закрашенных клеток,
+    /// у клеток, до которых никто не дотягивается, и у спорных.
+    pub heat: Vec<Option<Heat>>,
+}
+
+impl SampleBoardManager {
+    pub fn evaluate(&self) -> Evaluation {
+        let players = self.players();
+        let mut scores = vec![Score::default(); players];
+        let mut heat = vec![None; self.board.len()];
+        let mut grouped = vec![false; self.board.len()];
+        for y in 0..self.max_y {
+            for x in 0..self.max_x {
+                let offset = y * self.max_x + x;
+                let cell = self.board[offset];
+                match cell.kind() {
+                    CellKind::Empty | CellKind::Cross => {
+                        let reach: Vec<u8> = (0..players)
+                            .map(|p| {
+                                if cell.is_active(p) {
+                                    cell.activity(p)
+                                } else {
+                                    0
+                                }
+                            })
+                            .collect();
+                        for (player, &activity) in reach.iter().enumerate() {
+                            scores[player].mobility += activity as usize;
+                        }
+                        let mut reaching = (0..players).filter(|&p| reach[p] != 0);
+                        if let (Some(player), None) = (reaching.next(), reaching.next()) {
+                            scores[player].territory += 1;
+                        }
+                        heat[offset] = leader(&reach);
+                        if cell.kind() == CellKind::Cross && cell.is_important() {
+                            scores[cell.player()].important_crosses += 1;
+                        }
+                    }
+                    CellKind::Filled if !grouped[offset] => {
+                        for (x, y) in self.filled_group((x, y)) {
+                            grouped[y * self.max_x + x] = true;
+                        }
+                        let score = &mut scores[cell.player()];
+                        if cell.is_alive() {
+                            score.alive_groups += 1;
+                        } else {
+                            score.dead_groups += 1;
+                        }
+                    }
+                    CellKind::Filled | CellKind::Border => {}
+                }
+            }
+        }
+        Evaluation { scores, heat }
+    }
+}
+
+/// Игрок с наибольшей активностью, если он один такой.
+fn leader(reach: &[u8]) -> Option<Heat> {
+    let mut best = (0, None);
+    let mut second = 0;
+    for (player, &activity) in reach.iter().enumerate() {
+        if activity > best.0 {
+            second = best.0;
+            best = (activity, Some(player));
+        } else if activity > second {
+            second = activity;
+        }
+    }
+    let player = best.1?;
+    (best.0 > second).then_some(Heat {
+        player,
+        margin: best.0 - second,
+    })
+}
@@ -0,0 +1,196 @@
+//! Дерево вариантов партии. Каждый узел — ход, его дети — ходы, которые
+//! делались после него. Первый ребёнок — главное продолжение, остальные —
+//! варианты в том порядке, в котором их сыграли.
+//!
+//! `CrossesCore::log` всегда остаётся одной из линий этого дерева: от корня
+//! до листа, к которому ведут главные продолжения.
+use serde::{Deserialize, Serialize};
+
+pub const ROOT: usize = 0;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameTree {
+    nodes: Vec<Node>,
+}
+#[derive(Clone, Serialize, Deserialize)]
+struct Node {
+    /// У корня не используется.
+    index: (usize, usize),
+    parent: usize,
+    children: Vec<usize>,
+}
+
+impl Default for GameTree {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node {
+                index: (0, 0),
+                parent: ROOT,
+                children: Vec::new(),
+            }],
+        }
+    }
+}
+
+impl GameTree {
+    pub fn index(&self, node: usize) -> (usize, usize) {
+        self.nodes[node].index
+    }
+    pub fn children(&self, node: usize) -> &[usize] {
+        &self.nodes[node].children
+    }
+    pub fn child(&self, node: usize, index: (usize, usize)) -> Option<usize> {
+        self.children(node)
+            .iter()
+            .copied()
+            .find(|&child| self.index(child) == index)
+    }
+    /// Узел, к которому ведёт `line` от корня.
+    pub fn find(&self, line: &[(usize, usize)]) -> Option<usize> {
+        line.iter()
+            .try_fold(ROOT, |node, &index| self.child(node, index))
+    }
+    /// Как `find`, но недостающие ходы добавляются как новые варианты.
+    pub fn insert(&mut self, line: &[(usize, usize)]) -> usize {
+        let mut node = ROOT;
+        for &index in line {
+            node = match self.child(node, index) {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node {
+                        index,
+                        parent: node,
+                        children: Vec::new(),
+                    });
+                    self.nodes[node].children.push(child);
+                    child
+                }
+            };
+        }
+        node
+    }
+    /// Ходы главного продолжения после `node`.
+    pub fn main_line(&self, mut node: usize) -> Vec<(usize, usize)> {
+        let mut line = Vec::new();
+        while let Some(&child) = self.children(node).first() {
+            line.push(self.index(child));
+            node = child;
+        }
+        line
+    }
+    /// Все ссылки между узлами ведут к существующим узлам и друг к другу.
+    /// Дети добавляются после родителя, поэтому циклов в таком дереве нет.
+    pub fn is_consistent(&self) -> bool {
+        !self.nodes.is_empty()
+            && self
+                .nodes
+                .iter()
+                .enumerate()
+                .all(|(node, Node { children, .. })| {
+                    children.iter().all(|&child| {
+                        child > node
+                            && self.nodes.get(child).map(|child| child.parent) == Some(node)
+                    })
+                })
+    }
+    /// Делает `line` главной линией: каждый её ход становится первым среди братьев.
+    pub fn promote(&mut self, line: &[(usize, usize)]) {
+        let mut node = self.insert(line);
+        while node != ROOT {
+            let parent = self.nodes[node].parent;
+            let children = &mut self.nodes[parent].children;
+            let position = children.iter().position(|&child| child == node).unwrap();
+            children[..=position].rotate_right(1);
+            node = parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_core::CrossesCore;
+
+    const MAIN: [(usize, usize); 3] = [(1, 0), (1, 1), (2, 2)];
+    const VARIATION: [(usize, usize); 3] = [(1, 0), (2, 0), (3, 0)];
+
+    #[test]
+    fn insert_and_find() {
+        let mut tree = GameTree::default();
+        assert_eq!(tree.find(&[]), Some(ROOT));
+        assert_eq!(tree.find(&MAIN), None);
+
+        let leaf = tree.insert(&MAIN);
+        assert_eq!(tree.find(&MAIN), Some(leaf));
+        assert_eq!(tree.index(leaf), (2, 2));
+        assert_eq!(tree.insert(&MAIN), leaf, "nothing new to insert");
+
+        tree.insert(&VARIATION);
+        let first = tree.find(&MAIN[..1]).unwrap();
+        assert_eq!(tree.children(ROOT), [first]);
+        assert_eq!(tree.children(first).len(), 2);
+        assert_eq!(tree.child(first, (2, 0)), tree.find(&VARIATION[..2]));
+        assert_eq!(tree.child(first, (9, 9)), None);
+    }
+
+    #[test]
+    fn main_line_follows_first_children() {
+        let mut tree = GameTree::default();
+        tree.insert(&MAIN);
+        tree.insert(&VARIATION);
+        assert_eq!(tree.main_line(ROOT), MAIN);
+        let first = tree.find(&MAIN[..1]).unwrap();
+        assert_eq!(tree.main_line(first), MAIN[1..]);
+
+        tree.promote(&VARIATION[..2]);
+        assert_eq!(tree.main_line(ROOT), VARIATION);
+        // Прежняя главная линия осталась вариантом.
+        assert!(tree.find(&MAIN).is_some());
+    }
+
+    #[test]
+    fn broken_links_are_found() {
+        let mut tree = GameTree::default();
+        tree.insert(&MAIN);
+        tree.insert(&VARIATION);
+        assert!(tree.is_consistent());
+
+        let mut dangling = tree.clone();
+        dangling.nodes[ROOT].children.push(99);
+        assert!(!dangling.is_consistent());
+        let mut cycle = tree.clone();
+        cycle.nodes[2].children.push(1);
+        assert!(!cycle.is_consistent());
+        let mut orphan = tree;
+        orphan.nodes[3].parent = ROOT;
+        assert!(!orphan.is_consistent());
+        assert!(!GameTree { nodes: Vec::new() }.is_consistent());
+    }
+
+    #[test]
+    fn switching_variations() {
+        let mut game = CrossesCore::default();
+        game.play_first_moves(6);
+        let main = game.log.clone();
+        game.seek(3).unwrap();
+        let (x, y) = game.legal_moves()[1].index;
+        game.make_move(x, y).unwrap();
+        let variation = game.log.clone();
+        assert_eq!(variation.len(), 4);
+        assert!(!game.is_main_line());
+
+        game.back().unwrap();
+        assert_eq!(game.variations(), [main[3], (x, y)]);
+        game.choose_variation(main[3]).unwrap();
+        assert_eq!(game.log, main);
+        assert!(game.is_main_line());
+        assert_eq!(game.player_manager.current_move(), 3);
+        assert!(game.choose_variation((0, 0)).is_err());
+
+        game.choose_variation((x, y)).unwrap();
+        game.promote_variation();
+        assert!(game.is_main_line());
+        assert_eq!(game.tree.main_line(ROOT), variation);
+    }
+}
@@ -0,0 +1,169 @@
+//! Начальные расстановки: где стоят стартовые крестики, какие клетки
+//! закрашены с самого начала и где внутри доски стоят препятствия.
+use serde::{Deserialize, Serialize};
+
+use super::sample_board_manager::MAX_PLAYERS;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Layout {
+    /// Стартовый крестик каждого игрока по порядку. Сколько крестиков, столько и игроков.
+    pub starts: Vec<(usize, usize)>,
+    /// Клетки, закрашенные с самого начала, и их хозяева.
+    pub fills: Vec<((usize, usize), usize)>,
+    /// Клетки-границы внутри доски: в них нельзя ходить, и через них ничего не тянется.
+    pub obstacles: Vec<(usize, usize)>,
+}
+impl Layout {
+    pub fn preset(preset: Preset, max_x: usize, max_y: usize, players: usize) -> Self {
+        let starts = preset.starts(max_x, max_y);
+        Self {
+            starts: starts[..players.min(MAX_PLAYERS)].to_vec(),
+            fills: Vec::new(),
+            obstacles: Vec::new(),
+        }
+    }
+    pub fn players(&self) -> usize {
+        self.starts.len()
+    }
+}
+impl Default for Layout {
+    fn default() -> Self {
+        Self::preset(Preset::Corners, 10, 10, 2)
+    }
+}
+
+/// Готовые расстановки стартовых крестиков. Во всех первые два игрока
+/// стоят друг напротив друга, а расстановка симметрична для любого числа игроков.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Preset {
+    /// По углам доски.
+    Corners,
+    /// Посередине сторон.
+    Edges,
+    /// Вокруг центра, на четверти пути от углов.
+    Centre,
+}
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Corners, Preset::Edges, Preset::Centre];
+
+    pub fn starts(self, max_x: usize, max_y: usize) -> [(usize, usize); MAX_PLAYERS] {
+        let (left, top, right, bottom) = match self {
+            Preset::Corners => (0, 0, max_x - 1, max_y - 1),
+            Preset::Centre => (
+                max_x / 4,
+                max_y / 4,
+                max_x - 1 - max_x / 4,
+                max_y - 1 - max_y / 4,
+            ),
+            Preset::Edges => {
+                let (middle_x, middle_y) = ((max_x - 1) / 2, (max_y - 1) / 2);
+                return [
+                    (0, middle_y),
+                    (max_x - 1, max_y - 1 - middle_y),
+                    (max_x - 1 - middle_x, 0),
+                    (middle_x, max_y - 1),
+                ];
+            }
+        };
+        [(left, top), (right, bottom), (right, top), (left, bottom)]
+    }
+    /// Готовая расстановка, в которой игроки начинают со `starts`, если такая есть.
+    pub fn recognize(starts: &[(usize, usize)], max_x: usize, max_y: usize) -> Option<Preset> {
+        Preset::ALL
+            .into_iter()
+            .find(|&preset| preset.starts(max_x, max_y).get(..starts.len()) == Some(starts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_core::sample_board_manager::{
+        SampleBoardManager, SetupError, MAX_SIDE, MIN_PLAYERS, MIN_SIDE,
+    };
+    use crate::sample_core::{CrossesCore, CrossesError, GameSettings};
+
+    #[test]
+    fn presets_fit_any_board() {
+        for (max_x, max_y) in [(MIN_SIDE, MIN_SIDE), (MIN_SIDE, 7), (5, MIN_SIDE), (10, 10)] {
+            for preset in Preset::ALL {
+                let starts = preset.starts(max_x, max_y);
+                for (i, &(x, y)) in starts.iter().enumerate() {
+                    assert!(x < max_x && y < max_y, "{preset:?} on {max_x}x{max_y}");
+                    assert!(
+                        !starts[..i].contains(&(x, y)),
+                        "{preset:?} on {max_x}x{max_y}"
+                    );
+                }
+                for players in MIN_PLAYERS..=MAX_PLAYERS {
+                    let layout = Layout::preset(preset, max_x, max_y, players);
+                    assert!(SampleBoardManager::new(max_x, max_y, &layout).is_ok());
+                    // Совпадающие расстановки узнаются как первая из них.
+                    let recognized = Preset::recognize(&layout.starts, max_x, max_y).unwrap();
+                    assert_eq!(recognized.starts(max_x, max_y)[..players], layout.starts);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn presets_coincide_on_the_smallest_board() {
+        let corners = Preset::Corners.starts(MIN_SIDE, MIN_SIDE);
+        assert_eq!(corners, [(0, 0), (1, 1), (1, 0), (0, 1)]);
+        for preset in Preset::ALL {
+            assert_eq!(preset.starts(MIN_SIDE, MIN_SIDE), corners);
+            let starts = Layout::preset(preset, MIN_SIDE, MIN_SIDE, MAX_PLAYERS).starts;
+            assert_eq!(
+                Preset::recognize(&starts, MIN_SIDE, MIN_SIDE),
+                Some(Preset::Corners)
+            );
+        }
+    }
+
+    #[test]
+    fn presets_are_told_apart() {
+        for preset in Preset::ALL {
+            let starts = Layout::preset(preset, 10, 10, 2).starts;
+            assert_eq!(Preset::recognize(&starts, 10, 10), Some(preset));
+        }
+        assert_eq!(Preset::recognize(&[(0, 0), (5, 5)], 10, 10), None);
+        assert_eq!(Preset::recognize(&[(0, 0), (9, 9)], MAX_SIDE, 10), None);
+    }
+
+    #[test]
+    fn setup_errors() {
+        let layout = |starts: Vec<_>, fills| Layout {
+            starts,
+            fills,
+            obstacles: vec![(5, 5)],
+        };
+        let new = |layout: Layout| SampleBoardManager::new(10, 10, &layout);
+
+        assert!(matches!(
+            new(layout(vec![(0, 0), (0, 0)], Vec::new())),
+            Err(SetupError::Overlap((0, 0)))
+        ));
+        assert!(matches!(
+            new(layout(vec![(0, 0), (5, 5)], Vec::new())),
+            Err(SetupError::Overlap((5, 5)))
+        ));
+        assert!(matches!(
+            new(layout(vec![(0, 0), (9, 9)], vec![((9, 9), 0)])),
+            Err(SetupError::Overlap((9, 9)))
+        ));
+        assert!(matches!(
+            new(layout(vec![(0, 0), (9, 9)], vec![((1, 0), 2)])),
+            Err(SetupError::UnknownPlayer(2))
+        ));
+        assert!(new(layout(vec![(0, 0), (9, 9)], vec![((1, 0), 1)])).is_ok());
+
+        // Ходы за переход проверяет уже сама игра.
+        assert!(matches!(
+            CrossesCore::new(GameSettings {
+                moves_per_turn: 0,
+                ..Default::default()
+            }),
+            Err(CrossesError::SetupError(SetupError::NoMovesPerTurn))
+        ));
+    }
+}
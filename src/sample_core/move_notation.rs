@@ -0,0 +1,416 @@
+//! Запись ходов партии. Клетка записывается буквами столбца и номером строки:
+//! `a1` — это `(0, 0)`, `j10` — `(9, 9)`, после `z` идут `aa`, `ab` и так далее.
+//! Закрашивание чужого крестика помечается звёздочкой: `c3*`.
+//!
+//! Ходы группируются по переходам хода, перед каждой группой ставится её номер:
+//!
+//! ```text
+//! 1. b1 b2 c3 d4 2. i10 h9 h8 g7 3. e5 f6 g7* ...
+//! ```
+//!
+//! Номера групп при чтении необязательны, но если они указаны, то проверяются.
+//!
+//! Варианты записываются в скобках сразу после хода, который они заменяют.
+//! Если вариант начинается посреди перехода хода, номер пишется с многоточием:
+//!
+//! ```text
+//! 1. b1 b2 c3 d4 2. i10 h9 (2... g9 f8 f7) 2... h8 g7 (2... h7) 3. ...
+//! ```
+use std::error::Error;
+use std::fmt::{Display, Write};
+
+use crosses_core::board_manager::{BoardManager, Cell, CellKind};
+
+use super::game_tree::{self, GameTree};
+use super::{CrossesCore, CrossesError};
+
+impl CrossesCore {
+    /// Записывает всё дерево вариантов, включая отменённые ходы.
+    pub fn notation(&self) -> Result<String, CrossesError> {
+        let mut tree = self.tree.clone();
+        tree.insert(&self.log);
+        self.write_tree(&tree)
+    }
+    /// Записывает только текущую линию, без вариантов.
+    pub fn line_notation(&self) -> Result<String, CrossesError> {
+        let mut tree = GameTree::default();
+        tree.insert(&self.log);
+        self.write_tree(&tree)
+    }
+    fn write_tree(&self, tree: &GameTree) -> Result<String, CrossesError> {
+        let mut replay = self.start_position()?;
+        let mut out = String::new();
+        if let Some((&first, alternatives)) = tree.children(game_tree::ROOT).split_first() {
+            write_line(
+                tree,
+                first,
+                alternatives,
+                &mut replay,
+                &mut out,
+                Turn::default(),
+            )?;
+        }
+        Ok(out)
+    }
+    /// Проигрывает записанные ходы поверх текущей позиции, варианты добавляются в дерево.
+    /// Чтобы восстановить партию, вызывается на только что созданной игре.
+    pub fn replay(&mut self, text: &str) -> Result<(), NotationError> {
+        let mut turn = Turn::default();
+        // Счётчик перед последним сделанным ходом, к нему возвращается вариант.
+        let mut before_last = turn;
+        let mut turn_announced = false;
+        let mut branches: Vec<Branch<'_>> = Vec::new();
+        for token in tokens(text) {
+            let error = |kind| NotationError {
+                token: token.to_string(),
+                offset: token.as_ptr() as usize - text.as_ptr() as usize,
+                kind,
+            };
+            if token == "(" {
+                let current_move = self.player_manager.current_move();
+                if turn_announced || current_move == 0 {
+                    return Err(error(NotationErrorKind::Parenthesis));
+                }
+                branches.push(Branch {
+                    open: token,
+                    depth: current_move - 1,
+                    main: self.log[current_move - 1],
+                    before: before_last,
+                    after: turn,
+                });
+                self.back()
+                    .map_err(|e| error(NotationErrorKind::IllegalMove(e)))?;
+                turn = before_last;
+                continue;
+            }
+            if token == ")" {
+                let branch = match branches.pop() {
+                    Some(branch) if !turn_announced => branch,
+                    _ => return Err(error(NotationErrorKind::Parenthesis)),
+                };
+                self.seek(branch.depth)
+                    .and_then(|()| self.choose_variation(branch.main))
+                    .and_then(|()| self.forward())
+                    .map_err(|e| error(NotationErrorKind::IllegalMove(e)))?;
+                (before_last, turn) = (branch.before, branch.after);
+                continue;
+            }
+            if let Some(number) = token.strip_suffix("...") {
+                let number: usize = number
+                    .parse()
+                    .map_err(|_| error(NotationErrorKind::BadToken))?;
+                if turn_announced || number != turn.number {
+                    return Err(error(NotationErrorKind::TurnNumber {
+                        expected: turn.number,
+                    }));
+                }
+                continue;
+            }
+            if let Some(number) = token.strip_suffix('.') {
+                let number: usize = number
+                    .parse()
+                    .map_err(|_| error(NotationErrorKind::BadToken))?;
+                if turn_announced || number != turn.number + 1 {
+                    return Err(error(NotationErrorKind::TurnNumber {
+                        expected: turn.number + 1,
+                    }));
+                }
+                turn_announced = true;
+                continue;
+            }
+            before_last = turn;
+            if !turn.next(self.player_manager.current_player()) && turn_announced {
+                return Err(error(NotationErrorKind::TurnNotOver { turn: turn.number }));
+            }
+            turn_announced = false;
+
+            let (index, fill) = match token.strip_suffix('*') {
+                Some(index) => (index, true),
+                None => (token, false),
+            };
+            let (x, y) = parse_index(index).ok_or_else(|| error(NotationErrorKind::BadToken))?;
+            if fill != (self.board_manager.get((x, y)).kind() == CellKind::Cross) {
+                return Err(error(NotationErrorKind::FillMark));
+            }
+            self.make_move(x, y)
+                .map_err(|e| error(NotationErrorKind::IllegalMove(e)))?;
+        }
+        if let Some(branch) = branches.pop() {
+            return Err(NotationError {
+                token: branch.open.to_string(),
+                offset: branch.open.as_ptr() as usize - text.as_ptr() as usize,
+                kind: NotationErrorKind::Parenthesis,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Номер перехода хода и тот, кто в нём ходит.
+#[derive(Clone, Copy, Default)]
+struct Turn {
+    number: usize,
+    player: Option<usize>,
+}
+impl Turn {
+    /// Учитывает ход `player`. `true`, если с него начался новый переход хода.
+    fn next(&mut self, player: usize) -> bool {
+        if self.player == Some(player) {
+            return false;
+        }
+        self.player = Some(player);
+        self.number += 1;
+        true
+    }
+}
+
+/// Открытый вариант при чтении записи.
+struct Branch<'a> {
+    open: &'a str,
+    /// Сколько ходов сделано перед ходом, который заменяет вариант.
+    depth: usize,
+    /// Ход главной линии, который заменяет вариант.
+    main: (usize, usize),
+    before: Turn,
+    after: Turn,
+}
+
+/// Пишет ход `first`, варианты вместо него в скобках и всё, что идёт дальше
+/// по главным продолжениям. `replay` стоит в позиции перед `first`.
+fn write_line<'a>(
+    tree: &'a GameTree,
+    mut first: usize,
+    mut alternatives: &'a [usize],
+    replay: &mut CrossesCore,
+    out: &mut String,
+    mut turn: Turn,
+) -> Result<(), CrossesError> {
+    // Номер пишется в начале варианта и после вложенных вариантов.
+    let mut number = true;
+    loop {
+        let depth = replay.player_manager.current_move();
+        let before = turn;
+        write_move(tree.index(first), replay, out, &mut turn, number)?;
+        number = false;
+        if !alternatives.is_empty() {
+            for &alternative in alternatives {
+                replay.seek(depth)?;
+                out.push_str(" (");
+                write_line(tree, alternative, &[], replay, out, before)?;
+                out.push(')');
+            }
+            replay.seek(depth)?;
+            let (x, y) = tree.index(first);
+            replay.make_move(x, y)?;
+            number = true;
+        }
+        match tree.children(first).split_first() {
+            Some((&main, rest)) => (first, alternatives) = (main, rest),
+            None => return Ok(()),
+        }
+    }
+}
+
+fn write_move(
+    (x, y): (usize, usize),
+    replay: &mut CrossesCore,
+    out: &mut String,
+    turn: &mut Turn,
+    number: bool,
+) -> Result<(), CrossesError> {
+    if !out.is_empty() && !out.ends_with('(') {
+        out.push(' ');
+    }
+    if turn.next(replay.player_manager.current_player()) {
+        write!(out, "{}. ", turn.number).unwrap();
+    } else if number {
+        write!(out, "{}... ", turn.number).unwrap();
+    }
+    let fill = replay.board_manager.get((x, y)).kind() == CellKind::Cross;
+    replay.make_move(x, y)?;
+    write!(
+        out,
+        "{}{}",
+        format_index((x, y)),
+        if fill { "*" } else { "" }
+    )
+    .unwrap();
+    Ok(())
+}
+
+/// Делит текст по пробелам, скобки всегда отдельные токены.
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace().flat_map(|word| {
+        let mut rest = word;
+        std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            let end = match rest.find(['(', ')']) {
+                Some(0) => 1,
+                Some(end) => end,
+                None => rest.len(),
+            };
+            let (token, tail) = rest.split_at(end);
+            rest = tail;
+            Some(token)
+        })
+    })
+}
+
+pub fn format_index((x, y): (usize, usize)) -> String {
+    format!("{}{}", column_name(x), y + 1)
+}
+
+pub fn column_name(mut x: usize) -> String {
+    let mut column = Vec::new();
+    loop {
+        column.push(b'a' + (x % 26) as u8);
+        if x < 26 {
+            break;
+        }
+        x = x / 26 - 1;
+    }
+    column.reverse();
+    String::from_utf8(column).unwrap()
+}
+
+pub fn parse_index(text: &str) -> Option<(usize, usize)> {
+    let split = text.find(|c: char| c.is_ascii_digit())?;
+    let (column, row) = text.split_at(split);
+    if column.is_empty() || !column.bytes().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    let x = column.bytes().try_fold(0usize, |x, c| {
+        x.checked_mul(26)?.checked_add((c - b'a') as usize + 1)
+    })? - 1;
+    let row: usize = row.parse().ok()?;
+    Some((x, row.checked_sub(1)?))
+}
+
+#[derive(Debug)]
+pub struct NotationError {
+    pub token: String,
+    /// Смещение токена от начала текста в байтах.
+    pub offset: usize,
+    pub kind: NotationErrorKind,
+}
+#[derive(Debug)]
+pub enum NotationErrorKind {
+    BadToken,
+    TurnNumber { expected: usize },
+    TurnNotOver { turn: usize },
+    FillMark,
+    Parenthesis,
+    IllegalMove(CrossesError),
+}
+impl Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" at {}: ", self.token, self.offset)?;
+        match &self.kind {
+            NotationErrorKind::BadToken => write!(f, "not a move"),
+            NotationErrorKind::TurnNumber { expected } => {
+                write!(f, "expected turn number {expected} here")
+            }
+            NotationErrorKind::TurnNotOver { turn } => {
+                write!(
+                    f,
+                    "turn {turn} isn't over, the turn number before it is misplaced"
+                )
+            }
+            NotationErrorKind::FillMark => {
+                write!(f, "\"*\" must mark exactly the moves that fill a cross")
+            }
+            NotationErrorKind::Parenthesis => {
+                write!(
+                    f,
+                    "unbalanced parenthesis or a variation with no move to replace"
+                )
+            }
+            NotationErrorKind::IllegalMove(e) => write!(f, "{}", e),
+        }
+    }
+}
+impl Error for NotationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replayed(text: &str) -> Result<CrossesCore, NotationError> {
+        let mut game = CrossesCore::default();
+        game.replay(text)?;
+        Ok(game)
+    }
+
+    #[test]
+    fn indices() {
+        assert_eq!(format_index((0, 0)), "a1");
+        assert_eq!(format_index((9, 9)), "j10");
+        assert_eq!(column_name(25), "z");
+        assert_eq!(column_name(26), "aa");
+        assert_eq!(column_name(27 * 26), "aaa");
+        for index in [(0, 0), (25, 3), (26, 0), (701, 11), (702, 99)] {
+            assert_eq!(parse_index(&format_index(index)), Some(index));
+        }
+        for text in ["", "a", "1", "a0", "A1", "a1b", "-1"] {
+            assert_eq!(parse_index(text), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut game = CrossesCore::default();
+        game.play_first_moves(10);
+        let main = game.log.clone();
+        game.seek(6).unwrap();
+        let (x, y) = game.legal_moves()[1].index;
+        game.make_move(x, y).unwrap();
+        game.play_first_moves(3);
+        let variation = game.log.clone();
+        game.seek(2).unwrap();
+
+        let text = game.notation().unwrap();
+        assert!(text.starts_with("1. "));
+        assert_eq!(text.matches('(').count(), 1);
+
+        let loaded = replayed(&text).unwrap();
+        assert_eq!(loaded.notation().unwrap(), text);
+        assert!(loaded.tree.find(&main).is_some());
+        assert!(loaded.tree.find(&variation).is_some());
+        assert!(loaded.tree.main_line(game_tree::ROOT).starts_with(&main));
+    }
+
+    #[test]
+    fn error_offsets() {
+        let error = |text| replayed(text).err().unwrap();
+
+        let e = error("1. b1 ?");
+        assert!(matches!(e.kind, NotationErrorKind::BadToken));
+        assert_eq!((e.token.as_str(), e.offset), ("?", 6));
+
+        let e = error("1. b1 3. b2");
+        assert!(matches!(
+            e.kind,
+            NotationErrorKind::TurnNumber { expected: 2 }
+        ));
+        assert_eq!(e.offset, 6);
+
+        let e = error("1. e5");
+        assert!(matches!(e.kind, NotationErrorKind::IllegalMove(_)));
+        assert_eq!(e.offset, 3);
+
+        let e = error("1. j10");
+        assert!(matches!(e.kind, NotationErrorKind::FillMark));
+        assert_eq!(e.offset, 3);
+
+        let e = error("(1. b1)");
+        assert!(matches!(e.kind, NotationErrorKind::Parenthesis));
+        assert_eq!(e.offset, 0);
+
+        // Незакрытый вариант указывает на свою открывающую скобку.
+        let e = error("1. b1 b2 (1... c1");
+        assert!(matches!(e.kind, NotationErrorKind::Parenthesis));
+        assert_eq!((e.token.as_str(), e.offset), ("(", 9));
+    }
+}
@@ -0,0 +1,86 @@
+//! Предпросмотр хода: ход делается на копии доски, после чего копия
+//! сравнивается с настоящей доской.
+use crosses_core::board_manager::{BoardManager, Cell, CellKind};
+use crosses_core::player_manager::GameState;
+
+use super::sample_board_manager::MoveKind;
+use super::sample_cell::SampleCell;
+use super::{CrossesCore, CrossesError};
+
+pub struct MovePreview {
+    pub index: (usize, usize),
+    pub kind: MoveKind,
+    pub player: usize,
+    /// Клетки, в которые ходящий сможет сходить только после этого хода.
+    pub activated: Vec<(usize, usize)>,
+    /// Клетки, до которых хотя бы один из противников больше не дотягивается.
+    pub deactivated: Vec<(usize, usize)>,
+    /// Закрашенные клетки, которые станут мёртвыми.
+    pub killed: Vec<(usize, usize)>,
+    /// Клетки, которые перестанут быть важными.
+    pub lost_importance: Vec<(usize, usize)>,
+    pub moves_counter: Vec<usize>,
+    pub crosses_counter: Vec<usize>,
+}
+
+impl CrossesCore {
+    /// Показывает, что сделает ход в `(x, y)`, не меняя игру.
+    pub fn simulate_move(&self, x: usize, y: usize) -> Result<MovePreview, CrossesError> {
+        if let GameState::Ended(game_over) = self.player_manager.game_state() {
+            return Err(CrossesError::PlayerError(game_over));
+        }
+        let index = (x, y);
+        let player = self.player_manager.current_player();
+        let before = &self.board_manager;
+        let kind = before.check_move(index, player)?;
+        let mut after = before.clone();
+        after.make_move(index, player)?;
+
+        let mut preview = MovePreview {
+            index,
+            kind,
+            player,
+            activated: Vec::new(),
+            deactivated: Vec::new(),
+            killed: Vec::new(),
+            lost_importance: Vec::new(),
+            moves_counter: after.moves_counter.clone(),
+            crosses_counter: after.crosses_counter.clone(),
+        };
+        for y in 0..before.max_y {
+            for x in 0..before.max_x {
+                if (x, y) == index {
+                    continue;
+                }
+                let (old, new) = (before.get((x, y)), after.get((x, y)));
+                if !reachable(old, player) && reachable(new, player) {
+                    preview.activated.push((x, y));
+                }
+                if (0..before.players())
+                    .any(|enemy| enemy != player && reachable(old, enemy) && !reachable(new, enemy))
+                {
+                    preview.deactivated.push((x, y));
+                }
+                if old.kind() == CellKind::Filled
+                    && new.kind() == CellKind::Filled
+                    && old.is_alive()
+                    && !new.is_alive()
+                {
+                    preview.killed.push((x, y));
+                }
+                if matches!(old.kind(), CellKind::Cross | CellKind::Filled)
+                    && new.kind() == old.kind()
+                    && old.is_important()
+                    && !new.is_important()
+                {
+                    preview.lost_importance.push((x, y));
+                }
+            }
+        }
+        Ok(preview)
+    }
+}
+
+fn reachable(cell: SampleCell, player: usize) -> bool {
+    matches!(cell.kind(), CellKind::Empty | CellKind::Cross) && cell.is_active(player)
+}
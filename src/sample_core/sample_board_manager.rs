@@ -1,62 +1,265 @@
+use super::layout::Layout;
 use super::sample_cell::SampleCell;
-use crosses_core::board_manager::{self, init, Cell, CellKind};
+use crosses_core::board_manager::{
+    self, activate_around, deactivate_around, init, is_alive_filled_around,
+    mark_adjacent_as_important, BoardManager, Cell, CellKind,
+};
 use serde::{Deserialize, Serialize};
-use std::{collections::VecDeque, ops::ControlFlow};
+use std::{collections::VecDeque, error::Error, fmt::Display, ops::ControlFlow};
 
+pub const MIN_SIDE: usize = 2;
+pub const MAX_SIDE: usize = 64;
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 4;
+/// Цвета игроков по порядку.
+pub const PLAYER_COLORS: [&str; MAX_PLAYERS] = ["blue", "red", "green", "yellow"];
+
+/// Доска хранится построчно: клетка `(x, y)` лежит в `board[y * max_x + x]`.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SampleBoardManager {
-    pub board: [[SampleCell; 16]; 16],
+    pub board: Vec<SampleCell>,
     pub max_x: usize,
     pub max_y: usize,
-    pub moves_counter: [usize; 2],
-    pub crosses_counter: [usize; 2],
+    /// Счётчики по игрокам.
+    pub moves_counter: Vec<usize>,
+    pub crosses_counter: Vec<usize>,
+    /// Хеш Зобриста позиции, см. `SampleCell::state`. Обновляется в `make_move`
+    /// и `cancel_move` по клеткам, которые отдавал `get_mut`. Прямые записи
+    /// в `board` его не обновляют, после них нужно вызвать `rehash`.
+    #[serde(default)]
+    pub hash: u64,
+    /// Клетки, отданные `get_mut` с последнего обновления хеша, и их прежние значения.
+    #[serde(skip)]
+    changed: Vec<(usize, SampleCell)>,
+    /// Клетка, которую отдаёт `get_mut` для индексов за пределами доски.
+    /// Всё, что в неё записано, забывается при следующем обращении.
+    #[serde(skip)]
+    outside: SampleCell,
 }
 
 impl SampleBoardManager {
-    pub fn new(max_x: usize, max_y: usize) -> Self {
-        assert!(max_x > 1 && max_y > 1);
+    pub fn new(max_x: usize, max_y: usize, layout: &Layout) -> Result<Self, SetupError> {
+        if !(MIN_SIDE..=MAX_SIDE).contains(&max_x) || !(MIN_SIDE..=MAX_SIDE).contains(&max_y) {
+            return Err(SetupError::Size { max_x, max_y });
+        }
+        let players = layout.players();
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&players) {
+            return Err(SetupError::Players(players));
+        }
         let mut manager = Self {
-            board: [[SampleCell::BORDER; 16]; 16],
+            board: vec![SampleCell::EMPTY; max_x * max_y],
             max_x,
             max_y,
-            moves_counter: [0, 0],
-            crosses_counter: [1, 1],
+            moves_counter: vec![0; players],
+            crosses_counter: vec![1; players],
+            hash: 0,
+            changed: Vec::new(),
+            outside: SampleCell::BORDER,
         };
-        for x in 0..max_x {
-            for y in 0..max_y {
-                manager.board[x][y] = SampleCell {
-                    data: 0b00100000,
-                    activity: 0,
-                };
+        for &index in &layout.obstacles {
+            manager.place(index, SampleCell::BORDER)?;
+        }
+        for (player, &start) in layout.starts.iter().enumerate() {
+            manager.place(start, SampleCell::start(player))?;
+            init(&mut manager, start, player);
+        }
+        if !layout.fills.is_empty() {
+            for &(index, player) in &layout.fills {
+                if player >= players {
+                    return Err(SetupError::UnknownPlayer(player));
+                }
+                manager.place(index, SampleCell::seed_fill(player))?;
             }
+            // `init` умеет ставить только одиночные крестики, а закрашенные клетки
+            // меняют активность и живость вокруг, поэтому всё пересчитывается заново.
+            manager.rebuild();
         }
-        manager.board[0][0] = SampleCell {
-            data: 0b01000000,
-            activity: 0,
-        };
-        init(&mut manager, (0, 0), false);
-        manager.board[max_x - 1][max_y - 1] = SampleCell {
-            data: 0b01010000,
-            activity: 0,
-        };
-        init(&mut manager, (max_x - 1, max_y - 1), true);
-        manager
+        manager.rehash();
+        Ok(manager)
+    }
+    /// Ставит клетку расстановки в пустую клетку доски.
+    fn place(&mut self, index: (usize, usize), cell: SampleCell) -> Result<(), SetupError> {
+        let offset = self.offset(index).ok_or(SetupError::OutOfBounds(index))?;
+        if self.board[offset].kind() != CellKind::Empty {
+            return Err(SetupError::Overlap(index));
+        }
+        self.board[offset] = cell;
+        Ok(())
+    }
+    /// Хеш позиции, посчитанный заново по всей доске.
+    pub fn full_hash(&self) -> u64 {
+        self.board
+            .iter()
+            .enumerate()
+            .fold(0, |hash, (offset, &cell)| hash ^ zobrist(offset, cell))
+    }
+    pub fn rehash(&mut self) {
+        self.changed.clear();
+        self.hash = self.full_hash();
+    }
+    /// Учитывает в хеше клетки, изменённые через `get_mut`.
+    fn update_hash(&mut self) {
+        // Сортировка устойчивая, поэтому у каждой клетки остаётся самое старое значение.
+        self.changed.sort_by_key(|&(offset, _)| offset);
+        self.changed.dedup_by_key(|&mut (offset, _)| offset);
+        for &(offset, old) in &self.changed {
+            self.hash ^= zobrist(offset, old) ^ zobrist(offset, self.board[offset]);
+        }
+        self.changed.clear();
+    }
+    pub fn players(&self) -> usize {
+        self.moves_counter.len()
     }
     pub fn clear_checked(&mut self) {
-        for column in self.board.iter_mut() {
-            for cell in column.iter_mut() {
-                cell.set_checked(false)
+        for cell in self.board.iter_mut() {
+            cell.set_checked(false)
+        }
+    }
+    /// Проверяет, можно ли `player` сходить в `index`, ничего не меняя.
+    /// Ошибки те же, что вернул бы `make_move`.
+    pub fn check_move(
+        &self,
+        index: (usize, usize),
+        player: usize,
+    ) -> Result<MoveKind, board_manager::BoardError> {
+        let cell = self.get(index);
+        match cell.kind() {
+            CellKind::Empty => {
+                if !cell.is_active(player) {
+                    return Err(board_manager::BoardError::OutOfReach);
+                }
+                Ok(MoveKind::Cross)
+            }
+            CellKind::Cross => {
+                if cell.player() == player {
+                    return Err(board_manager::BoardError::SelfFill);
+                }
+                if !cell.is_active(player) {
+                    return Err(board_manager::BoardError::OutOfReach);
+                }
+                Ok(MoveKind::Fill)
+            }
+            CellKind::Filled => Err(board_manager::BoardError::DoubleFill),
+            CellKind::Border => Err(board_manager::BoardError::BorderHit),
+        }
+    }
+    /// Все ходы, доступные `player`, построчно.
+    pub fn legal_moves(&self, player: usize) -> Vec<LegalMove> {
+        let mut moves = Vec::new();
+        for y in 0..self.max_y {
+            for x in 0..self.max_x {
+                if let Ok(kind) = self.check_move((x, y), player) {
+                    moves.push(LegalMove {
+                        index: (x, y),
+                        kind,
+                    });
+                }
+            }
+        }
+        moves
+    }
+    /// Закрашенные клетки того же игрока, связанные с `start` так же, как их обходит `traverse`.
+    pub fn filled_group(&self, start: (usize, usize)) -> Vec<(usize, usize)> {
+        std::iter::once(start)
+            .chain(self.group_links(start).into_iter().map(|(_, to)| to))
+            .collect()
+    }
+    /// Рёбра обхода группы из `start` в ширину, как в `traverse`: каждая клетка
+    /// группы, кроме `start`, связана с той, от которой до неё дошёл обход.
+    pub fn group_links(&self, start: (usize, usize)) -> Vec<((usize, usize), (usize, usize))> {
+        let player = self.get(start).player();
+        let mut links = Vec::new();
+        let mut grouped = vec![false; self.board.len()];
+        if let Some(offset) = self.offset(start) {
+            grouped[offset] = true;
+        }
+        let mut queue = VecDeque::from([start]);
+        while let Some(index) = queue.pop_front() {
+            for adjacent in self.adjacent(index) {
+                let cell = self.get(adjacent);
+                if cell.kind() != CellKind::Filled || cell.player() != player {
+                    continue;
+                }
+                let offset = self.offset(adjacent).unwrap();
+                if !grouped[offset] {
+                    grouped[offset] = true;
+                    links.push((index, adjacent));
+                    queue.push_back(adjacent);
+                }
+            }
+        }
+        links
+    }
+    fn offset(&self, index: (usize, usize)) -> Option<usize> {
+        (index.0 < self.max_x && index.1 < self.max_y).then(|| index.1 * self.max_x + index.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveKind {
+    /// Крестик в пустую клетку.
+    Cross,
+    /// Закрашивание крестика противника.
+    Fill,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegalMove {
+    pub index: (usize, usize),
+    pub kind: MoveKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SetupError {
+    Size {
+        max_x: usize,
+        max_y: usize,
+    },
+    Players(usize),
+    OutOfBounds((usize, usize)),
+    /// Две клетки расстановки в одном месте.
+    Overlap((usize, usize)),
+    UnknownPlayer(usize),
+    /// Переход хода без единого хода.
+    NoMovesPerTurn,
+}
+impl Display for SetupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetupError::Size { max_x, max_y } => write!(
+                f,
+                "board {max_x}x{max_y} is out of range (sides must be {MIN_SIDE} to {MAX_SIDE})"
+            ),
+            SetupError::Players(players) => write!(
+                f,
+                "{players} players can't play (must be {MIN_PLAYERS} to {MAX_PLAYERS})"
+            ),
+            SetupError::OutOfBounds((x, y)) => {
+                write!(f, "cell x: {x} y: {y} is outside of the board")
+            }
+            SetupError::Overlap((x, y)) => {
+                write!(f, "cell x: {x} y: {y} is taken twice by the layout")
+            }
+            SetupError::UnknownPlayer(player) => {
+                write!(
+                    f,
+                    "layout fills a cell for player {player} who doesn't play"
+                )
             }
+            SetupError::NoMovesPerTurn => write!(f, "a turn must have at least one move"),
         }
     }
 }
+impl Error for SetupError {}
 
 impl board_manager::BoardManager for SampleBoardManager {
     type Index = (usize, usize);
 
     type Cell = SampleCell;
 
-    fn adjacent(&mut self, index: Self::Index) -> impl IntoIterator<Item = Self::Index> {
+    type Adjacent = [Self::Index; 8];
+
+    fn adjacent(&self, index: Self::Index) -> Self::Adjacent {
         [
             (index.0.wrapping_sub(1), index.1.wrapping_sub(1)),
             (index.0, index.1.wrapping_sub(1)),
@@ -70,50 +273,54 @@ impl board_manager::BoardManager for SampleBoardManager {
     }
 
     fn get(&self, index: Self::Index) -> Self::Cell {
-        if index.0 < self.max_x && index.1 < self.max_y {
-            self.board[index.0][index.1]
-        } else {
-            SampleCell::BORDER
+        match self.offset(index) {
+            Some(offset) => self.board[offset],
+            None => SampleCell::BORDER,
         }
     }
 
-    fn get_mut(&self, index: Self::Index) -> &mut Self::Cell {
-        todo!()
+    fn get_mut(&mut self, index: Self::Index) -> &mut Self::Cell {
+        match self.offset(index) {
+            Some(offset) => {
+                self.changed.push((offset, self.board[offset]));
+                &mut self.board[offset]
+            }
+            None => {
+                self.outside = SampleCell::BORDER;
+                &mut self.outside
+            }
+        }
     }
 
-    type Adjacent;
-
     fn traverse(
         &mut self,
         index: Self::Index,
-        mut action: impl FnMut(&mut Self, Self::Index) -> std::ops::ControlFlow<Self::Index, ()>,
+        mut action: impl FnMut(&mut Self, Self::Index) -> ControlFlow<Self::Index, ()>,
     ) -> Option<Self::Index> {
         self.clear_checked();
-        let mut result = None;
-        let mut cell = self.get(index);
-        let player = cell.player();
+        let player = self.get(index).player();
         let mut queue = VecDeque::new();
         queue.push_back(index);
-        cell.set_checked(true);
-        self.set(index, cell);
-        action(self, index);
+        self.get_mut(index).set_checked(true);
+        if let ControlFlow::Break(found) = action(self, index) {
+            return Some(found);
+        }
         while let Some(index) = queue.pop_front() {
-            for adjacent_index in SampleBoardManager::adjacent(index) {
-                if !self.get(adjacent_index).is_checked() {
-                    if let ControlFlow::Break(new_index) = action(self, adjacent_index) {
-                        result = Some(new_index);
-                        break;
-                    }
-                    let mut cell = self.get(adjacent_index);
-                    if cell.kind() == CellKind::Filled && cell.player() == player {
-                        queue.push_back(adjacent_index)
-                    }
-                    cell.set_checked(true);
-                    self.set(adjacent_index, cell);
+            for adjacent_index in self.adjacent(index) {
+                if self.get(adjacent_index).is_checked() {
+                    continue;
                 }
+                if let ControlFlow::Break(found) = action(self, adjacent_index) {
+                    return Some(found);
+                }
+                let cell = self.get_mut(adjacent_index);
+                if cell.kind() == CellKind::Filled && cell.player() == player {
+                    queue.push_back(adjacent_index)
+                }
+                cell.set_checked(true);
             }
         }
-        return result;
+        None
     }
 
     fn update_counter(
@@ -122,7 +329,15 @@ impl board_manager::BoardManager for SampleBoardManager {
         kind: board_manager::CounterKind,
         op: board_manager::CounterOp,
     ) {
-        todo!()
+        let counters = match kind {
+            board_manager::CounterKind::Moves => &mut self.moves_counter,
+            board_manager::CounterKind::Crosses => &mut self.crosses_counter,
+        };
+        let counter = &mut counters[player];
+        match op {
+            board_manager::CounterOp::Add => *counter += 1,
+            board_manager::CounterOp::Sub => *counter -= 1,
+        }
     }
 
     fn revive(&mut self, index: Self::Index, mut revive: impl FnMut(&mut Self, Self::Index)) {
@@ -152,12 +367,11 @@ impl board_manager::BoardManager for SampleBoardManager {
         index: Self::Index,
         player: board_manager::Player<Self>,
     ) -> Result<(), board_manager::BoardError> {
+        let kind = self.check_move(index, player)?;
+        let players = self.players();
         let cell = self.get_mut(index);
-        match cell.kind() {
-            CellKind::Empty => {
-                if !cell.is_active(player) {
-                    return Err(board_manager::BoardError::OutOfReach);
-                }
+        match kind {
+            MoveKind::Cross => {
                 cell.cross_out(player);
                 let should_set_important = activate_around(self, index, player);
                 self.get_mut(index).set_important(should_set_important);
@@ -172,26 +386,24 @@ impl board_manager::BoardManager for SampleBoardManager {
                     board_manager::CounterOp::Sub,
                 );
             }
-            CellKind::Cross => {
-                if cell.player() == player {
-                    return Err(board_manager::BoardError::SelfFill);
-                }
-                if !cell.is_active(player) {
-                    return Err(board_manager::BoardError::OutOfReach);
-                }
+            MoveKind::Fill => {
                 let was_important = cell.is_important();
                 let previous_player = cell.player();
+                // Крестик был ходом для всех, кто до него дотягивался, а не только для ходящего.
+                let reaching: Vec<_> = (0..players).filter(|&p| cell.is_active(p)).collect();
                 cell.fill(player);
-                self.update_counter(
-                    player,
-                    board_manager::CounterKind::Crosses,
-                    board_manager::CounterOp::Sub,
-                );
                 self.update_counter(
                     previous_player,
-                    board_manager::CounterKind::Moves,
+                    board_manager::CounterKind::Crosses,
                     board_manager::CounterOp::Sub,
                 );
+                for reaching_player in reaching {
+                    self.update_counter(
+                        reaching_player,
+                        board_manager::CounterKind::Moves,
+                        board_manager::CounterOp::Sub,
+                    );
+                }
                 deactivate_around(self, index, previous_player, was_important);
                 let mut important = false;
                 if !is_alive_filled_around(self, index, player) {
@@ -201,9 +413,8 @@ impl board_manager::BoardManager for SampleBoardManager {
                 let should_set_important = activate_around(self, index, player) || important;
                 self.get_mut(index).set_important(should_set_important);
             }
-            CellKind::Filled => return Err(board_manager::BoardError::DoubleFill),
-            CellKind::Border => return Err(board_manager::BoardError::BorderHit),
         };
+        self.update_hash();
         Ok(())
     }
 
@@ -212,6 +423,7 @@ impl board_manager::BoardManager for SampleBoardManager {
         index: Self::Index,
         mut get_player: impl FnMut() -> board_manager::Player<Self>,
     ) -> Result<(), board_manager::BoardError> {
+        let players = self.players();
         let cell = self.get_mut(index);
         match cell.kind() {
             CellKind::Empty => return Err(board_manager::BoardError::EmptyCancel),
@@ -236,28 +448,45 @@ impl board_manager::BoardManager for SampleBoardManager {
                 let was_important = cell.is_important();
                 let previous_player = cell.player();
                 cell.remove_fill(player);
-                self.update_counter(
-                    previous_player,
-                    board_manager::CounterKind::Moves,
-                    board_manager::CounterOp::Add,
-                );
+                let reaching: Vec<_> = (0..players).filter(|&p| cell.is_active(p)).collect();
                 self.update_counter(
                     player,
                     board_manager::CounterKind::Crosses,
-                    board_manager::CounterOp::Sub,
+                    board_manager::CounterOp::Add,
                 );
+                for reaching_player in reaching {
+                    self.update_counter(
+                        reaching_player,
+                        board_manager::CounterKind::Moves,
+                        board_manager::CounterOp::Add,
+                    );
+                }
                 deactivate_around(self, index, previous_player, was_important);
                 let should_set_important = activate_around(self, index, player);
                 self.get_mut(index).set_important(should_set_important);
             }
             CellKind::Border => return Err(board_manager::BoardError::BorderHit),
         }
+        self.update_hash();
         Ok(())
     }
 }
 
+/// Случайное число для состояния клетки. Таблица на все клетки и состояния
+/// заняла бы мегабайты, поэтому число получается перемешиванием (splitmix64).
+fn zobrist(offset: usize, cell: SampleCell) -> u64 {
+    let state = cell.state();
+    if state == 0 {
+        return 0;
+    }
+    let mut z = ((offset as u64) << 8 | state as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ z >> 30).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ z >> 27).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ z >> 31
+}
+
 impl Default for SampleBoardManager {
     fn default() -> Self {
-        Self::new(10, 10)
+        Self::new(10, 10, &Layout::default()).unwrap()
     }
 }
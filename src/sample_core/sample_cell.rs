@@ -1,12 +1,13 @@
-use crosses_core::board_manager::{self, ActivationStatus, CellKind};
+use crosses_core::board_manager::{self, ActivationStatus, Cell, CellKind};
 use serde::{Deserialize, Serialize};
 
-const BORDER: u8 = 0b00;
-const EMPTY: u8 = 0b01;
-const CROSS: u8 = 0b10;
-const FILLED: u8 = 0b11;
+const BORDER: u16 = 0b00;
+const EMPTY: u16 = 0b01;
+const CROSS: u16 = 0b10;
+const FILLED: u16 = 0b11;
 
-const TYPE: u8 = 5;
+const ORIGIN: u8 = 8;
+const TYPE: u8 = 6;
 const PLAYER: u8 = 4;
 const IMPORTANCE: u8 = 3;
 const ALIVE: u8 = 2;
@@ -15,21 +16,23 @@ const CHECKED: u8 = 0;
 
 const ACTIVITY_SIZE: u8 = 2;
 /// Эта клетка имеет такую структуру:
-/// Резерв Тип Игрок Важность Живость Перегретость Проверенность
-/// (0)    00  0     0        0       0            0
-/// Плюс ещё сверху активность:
-/// Резерв Синий Красный
-/// (0000) 00    00
-#[derive(Clone, Copy, Serialize, Deserialize)]
+/// Резерв   Автор Тип Игрок Важность Живость Перегретость Проверенность
+/// (000000) 00    00  00    0        0       0            0
+/// Автор — игрок, поставивший крестик. У закрашенной клетки он остаётся прежним,
+/// чтобы отмена закрашивания знала, чей крестик вернуть.
+/// Плюс ещё сверху активность, по два бита на игрока:
+/// Жёлтый Зелёный Красный Синий
+/// 00     00      00      00
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
 pub struct SampleCell {
-    pub data: u8,
+    pub data: u16,
     pub activity: u8,
 }
 impl board_manager::Cell for SampleCell {
-    type Player = bool;
+    type Player = usize;
 
     fn kind(self) -> board_manager::CellKind {
-        match self.data >> TYPE & 0b11 {
+        match self.field(TYPE) {
             BORDER => CellKind::Border,
             EMPTY => CellKind::Empty,
             CROSS => CellKind::Cross,
@@ -39,7 +42,7 @@ impl board_manager::Cell for SampleCell {
     }
 
     fn player(self) -> Self::Player {
-        self.data >> PLAYER & 1 == 1
+        self.field(PLAYER) as usize
     }
 
     fn is_active(self, player: Self::Player) -> bool {
@@ -74,6 +77,7 @@ impl board_manager::Cell for SampleCell {
         assert_eq!(self.kind(), CellKind::Empty);
         self.set_type(CROSS);
         self.set_player(player);
+        self.set_field(ORIGIN, player as u16);
     }
 
     fn fill(&mut self, player: Self::Player) {
@@ -108,7 +112,7 @@ impl board_manager::Cell for SampleCell {
     fn deactivate(&mut self, player: Self::Player) -> ActivationStatus {
         let data = ActivityParser::new(self.activity, player);
         if data.activity <= 1 {
-            self.activity &= !data.filler << data.offset;
+            self.activity &= !(data.filler << data.offset);
             ActivationStatus::Zero
         } else {
             self.activity -= 1 << data.offset;
@@ -131,24 +135,65 @@ impl board_manager::Cell for SampleCell {
 
 impl SampleCell {
     pub const BORDER: Self = Self {
-        data: 0,
+        data: BORDER << TYPE,
         activity: 0,
     };
+    pub const EMPTY: Self = Self {
+        data: EMPTY << TYPE,
+        activity: 0,
+    };
+    /// Стартовый крестик игрока.
+    pub fn start(player: usize) -> Self {
+        let mut cell = Self::EMPTY;
+        cell.cross_out(player);
+        cell
+    }
+    /// Клетка, закрашенная игроком ещё до начала игры. Отменять её некому,
+    /// поэтому автором считается сам игрок.
+    pub fn seed_fill(player: usize) -> Self {
+        let mut cell = Self::start(player);
+        cell.fill(player);
+        cell
+    }
+    /// Игрок, поставивший крестик в эту клетку.
+    pub fn origin(self) -> usize {
+        assert!(self.kind() == CellKind::Cross || self.kind() == CellKind::Filled);
+        self.field(ORIGIN) as usize
+    }
+    /// То, что отличает клетку в позиции: тип, игрок, важность и живость.
+    /// Активность и служебные флаги выводятся из остальной доски и не учитываются.
+    /// У пустой клетки всегда 0.
+    pub fn state(self) -> u8 {
+        let (kind, important, alive) = match self.kind() {
+            CellKind::Empty => return 0,
+            CellKind::Border => return 1,
+            CellKind::Cross => (CROSS, self.is_important(), false),
+            CellKind::Filled => (FILLED, self.is_important(), self.is_alive()),
+        };
+        (kind as u8) << 4 | (self.player() as u8) << 2 | (important as u8) << 1 | alive as u8
+    }
     pub fn is_checked(self) -> bool {
         self.get(CHECKED)
     }
     pub fn set_checked(&mut self, new: bool) {
         self.set(CHECKED, new)
     }
-    pub fn activity(&self, player: bool) -> u8 {
+    pub fn activity(&self, player: usize) -> u8 {
         ActivityParser::new(self.activity, player).activity
     }
-    fn set_type(&mut self, new: u8) {
-        self.data &= !(0b11 << TYPE);
-        self.data |= new << TYPE;
+    fn set_type(&mut self, new: u16) {
+        self.set_field(TYPE, new);
+    }
+    fn set_player(&mut self, new: usize) {
+        self.set_field(PLAYER, new as u16)
+    }
+    /// Двухбитное поле по смещению `offset`.
+    fn field(self, offset: u8) -> u16 {
+        self.data >> offset & 0b11
     }
-    fn set_player(&mut self, new: bool) {
-        self.set(PLAYER, new)
+    fn set_field(&mut self, offset: u8, new: u16) {
+        self.data &= !(0b11 << offset);
+        self.data |= (new & 0b11) << offset;
     }
     fn set(&mut self, offset: u8, new: bool) {
         if new {
@@ -168,7 +213,7 @@ struct ActivityParser {
     activity: u8,
 }
 impl ActivityParser {
-    fn new(raw_activity: u8, player: bool) -> Self {
+    fn new(raw_activity: u8, player: usize) -> Self {
         let offset = ACTIVITY_SIZE * player as u8;
         let filler = (1 << ACTIVITY_SIZE) - 1;
         let activity = (raw_activity >> offset) & filler;
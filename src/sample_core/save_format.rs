@@ -0,0 +1,669 @@
+//! Текстовый формат сохранения партии. Пример:
+//!
+//! ```text
+//! crosses 6
+//! size 4x3
+//! turn 4
+//! start 0,0 3,2
+//! fills 2,2:r
+//! obstacles 3,0
+//! board
+//! bb.#
+//! .bb.
+//! ..Rr
+//! moves 3 0
+//! crosses 4 1
+//! hash 5d1c0a4e93b7f286
+//! log 1,0 1,1 2,1
+//! cursor 3
+//! clock 300000+5000 281400 300000
+//! notation 1. b1 b2 (1... c1) 1... c2
+//! ```
+//!
+//! * `size` — ширина и высота доски;
+//! * `turn` — количество ходов за один переход хода;
+//! * `start` — стартовые клетки игроков по порядку (в версии 1 этой строки нет,
+//!   игроки начинают в противоположных углах);
+//! * `fills` — клетки, закрашенные до начала игры, в виде `x,y:игрок`, где игрок —
+//!   буква из `board`; `obstacles` — препятствия внутри доски. Обе строки появились
+//!   в версии 4 и могут быть пустыми;
+//! * `board` — `max_y` строк по `max_x` символов: `#` граница, `.` пустая,
+//!   `b`/`r`/`g`/`y` крестик синих/красных/зелёных/жёлтых, заглавная буква —
+//!   клетка, закрашенная этим игроком;
+//! * `moves` и `crosses` — счётчики ходов и крестиков каждого игрока по порядку;
+//! * `hash` — хеш позиции (`SampleBoardManager::hash`) в шестнадцатеричном виде,
+//!   появился в версии 3;
+//! * `log` — ходы текущей линии в виде `x,y`, включая отменённые;
+//! * `cursor` — сколько ходов из лога сейчас сделано;
+//! * `clock` — контроль времени в виде `запас+добавка` и оставшееся время игроков
+//!   по порядку, всё в миллисекундах, или `-` для партии без контроля времени.
+//!   За временем идут выбывшие по времени в виде `игрок@ходов`: буква из `board`
+//!   и сколько ходов было сделано, когда у игрока кончилось время.
+//!   Появилась в версии 5, старые сохранения не ограничены по времени;
+//! * `notation` — всё дерево вариантов в записи ходов (см. `move_notation`).
+//!   Появилась в версии 6, в старых сохранениях дерево состоит из одного `log`.
+//!
+//! Пустые строки игнорируются. При загрузке весь лог проигрывается заново
+//! и отматывается до `cursor`, после чего доска и счётчики сверяются с записанными.
+use std::error::Error;
+use std::fmt::{Display, Write};
+use std::time::Duration;
+
+use crosses_core::board_manager::{BoardManager, Cell, CellKind};
+
+use super::clock::{Clock, TimeControl};
+use super::layout::{Layout, Preset};
+use super::move_notation::NotationError;
+use super::sample_board_manager::{MAX_PLAYERS, PLAYER_COLORS};
+use super::sample_cell::SampleCell;
+use super::{CrossesCore, CrossesError, GameSettings};
+
+pub const VERSION: u32 = 6;
+/// Буквы крестиков игроков по порядку — первые буквы их цветов.
+const PLAYER_CHARS: [char; MAX_PLAYERS] = {
+    let mut chars = ['.'; MAX_PLAYERS];
+    let mut player = 0;
+    while player < MAX_PLAYERS {
+        chars[player] = PLAYER_COLORS[player].as_bytes()[0] as char;
+        player += 1;
+    }
+    chars
+};
+
+impl CrossesCore {
+    pub fn export(&self) -> String {
+        let board = &self.board_manager;
+        let mut out = String::new();
+        writeln!(out, "crosses {VERSION}").unwrap();
+        writeln!(out, "size {}x{}", board.max_x, board.max_y).unwrap();
+        writeln!(out, "turn {}", self.settings.moves_per_turn).unwrap();
+        let layout = &self.settings.layout;
+        out.push_str("start");
+        for (x, y) in &layout.starts {
+            write!(out, " {x},{y}").unwrap();
+        }
+        out.push('\n');
+        out.push_str("fills");
+        for ((x, y), player) in &layout.fills {
+            write!(out, " {x},{y}:{}", PLAYER_CHARS[*player]).unwrap();
+        }
+        out.push('\n');
+        out.push_str("obstacles");
+        for (x, y) in &layout.obstacles {
+            write!(out, " {x},{y}").unwrap();
+        }
+        out.push('\n');
+        writeln!(out, "board").unwrap();
+        for y in 0..board.max_y {
+            for x in 0..board.max_x {
+                out.push(cell_to_char(board.get((x, y))));
+            }
+            out.push('\n');
+        }
+        out.push_str("moves");
+        for moves in &board.moves_counter {
+            write!(out, " {moves}").unwrap();
+        }
+        out.push('\n');
+        out.push_str("crosses");
+        for crosses in &board.crosses_counter {
+            write!(out, " {crosses}").unwrap();
+        }
+        out.push('\n');
+        writeln!(out, "hash {:016x}", board.hash).unwrap();
+        out.push_str("log");
+        for (x, y) in &self.log {
+            write!(out, " {x},{y}").unwrap();
+        }
+        out.push('\n');
+        writeln!(out, "cursor {}", self.player_manager.current_move()).unwrap();
+        match (self.settings.time_control, &self.clock) {
+            (Some(control), Some(clock)) => {
+                write!(
+                    out,
+                    "clock {}+{}",
+                    control.total.as_millis(),
+                    control.increment.as_millis()
+                )
+                .unwrap();
+                for remaining in &clock.remaining {
+                    write!(out, " {}", remaining.as_millis()).unwrap();
+                }
+                for (player, at) in &self.flags {
+                    write!(out, " {}@{at}", PLAYER_CHARS[*player]).unwrap();
+                }
+                out.push('\n');
+            }
+            _ => writeln!(out, "clock -").unwrap(),
+        }
+        // Дерево из сохранения приложения может не проигрываться целиком. Тогда
+        // записывается одна текущая линия, а если не проигрывается и она, то
+        // пустая запись, на которой загрузка остановится с ошибкой.
+        let notation = self
+            .notation()
+            .or_else(|_| self.line_notation())
+            .unwrap_or_default();
+        writeln!(out, "notation {notation}").unwrap();
+        out
+    }
+    pub fn import(text: &str) -> Result<Self, ImportError> {
+        let mut lines = Lines::new(text);
+
+        let (_, version) = lines.keyword("crosses")?;
+        let version = match version.parse() {
+            Ok(version @ 1..=VERSION) => version,
+            _ => return Err(ImportError::UnknownVersion(version.to_string())),
+        };
+
+        let (line, size) = lines.keyword("size")?;
+        let (max_x, max_y) = pair(line, size, 'x', "size")?;
+
+        let (line, turn) = lines.keyword("turn")?;
+        let moves_per_turn = number(line, turn, "turn")?;
+        if moves_per_turn == 0 {
+            return Err(ImportError::Malformed {
+                line,
+                expected: "turn",
+            });
+        }
+        let layout = if version >= 2 {
+            let (line, start) = lines.keyword("start")?;
+            let starts = start
+                .split_whitespace()
+                .map(|token| pair(line, token, ',', "start"))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut layout = Layout {
+                starts,
+                fills: Vec::new(),
+                obstacles: Vec::new(),
+            };
+            if version >= 4 {
+                let (line, fills) = lines.keyword("fills")?;
+                layout.fills = fills
+                    .split_whitespace()
+                    .map(|token| fill(line, token))
+                    .collect::<Result<_, _>>()?;
+                let (line, obstacles) = lines.keyword("obstacles")?;
+                layout.obstacles = obstacles
+                    .split_whitespace()
+                    .map(|token| pair(line, token, ',', "obstacles"))
+                    .collect::<Result<_, _>>()?;
+            }
+            layout
+        } else if max_x > 0 && max_y > 0 {
+            Layout::preset(Preset::Corners, max_x, max_y, 2)
+        } else {
+            Layout::default()
+        };
+        let settings = GameSettings {
+            max_x,
+            max_y,
+            moves_per_turn,
+            layout,
+            time_control: None,
+        };
+        let mut core = CrossesCore::new(settings).map_err(ImportError::Settings)?;
+
+        lines.keyword("board")?;
+        let mut position = Vec::with_capacity(max_y);
+        for _ in 0..max_y {
+            let (line, row) = lines.next()?;
+            let row: Vec<char> = row.chars().collect();
+            if row.len() != max_x || !row.iter().all(|&c| is_cell_char(c)) {
+                return Err(ImportError::Malformed {
+                    line,
+                    expected: "board",
+                });
+            }
+            position.push(row);
+        }
+
+        let (line, moves) = lines.keyword("moves")?;
+        let moves_counter = numbers(line, moves, "moves")?;
+        let (line, crosses) = lines.keyword("crosses")?;
+        let crosses_counter = numbers(line, crosses, "crosses")?;
+        let hash = if version >= 3 {
+            let (line, hash) = lines.keyword("hash")?;
+            let hash = u64::from_str_radix(hash, 16).map_err(|_| ImportError::Malformed {
+                line,
+                expected: "hash",
+            })?;
+            Some(hash)
+        } else {
+            None
+        };
+
+        let (line, log) = lines.keyword("log")?;
+        let log = log
+            .split_whitespace()
+            .map(|token| pair(line, token, ',', "log"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (line, cursor) = lines.keyword("cursor")?;
+        let cursor = number(line, cursor, "cursor")?;
+        if cursor > log.len() {
+            return Err(ImportError::BadCursor(cursor));
+        }
+        let clock = if version >= 5 {
+            let (line, clock) = lines.keyword("clock")?;
+            self::clock(line, clock, core.settings.players(), log.len())?
+        } else {
+            None
+        };
+        // Выбывшие по времени меняют очерёдность ходов, поэтому известны до проигрывания.
+        if let Some((_, _, flags)) = &clock {
+            core.flags = flags.clone();
+            core.resign_flags();
+        }
+        let notation = if version >= 6 {
+            Some(lines.keyword("notation")?.1)
+        } else {
+            None
+        };
+
+        for (number, &(x, y)) in log.iter().enumerate() {
+            core.make_move(x, y)
+                .map_err(|error| ImportError::IllegalMove {
+                    number: number + 1,
+                    error,
+                })?;
+        }
+        core.seek(cursor)
+            .expect("moves that were just made can be taken back");
+
+        for (y, row) in position.iter().enumerate() {
+            for (x, &expected) in row.iter().enumerate() {
+                if cell_to_char(core.board_manager.get((x, y))) != expected {
+                    return Err(ImportError::PositionMismatch { x, y });
+                }
+            }
+        }
+        let board = &core.board_manager;
+        if board.moves_counter != moves_counter || board.crosses_counter != crosses_counter {
+            return Err(ImportError::CounterMismatch);
+        }
+        if hash.is_some_and(|hash| hash != board.hash) {
+            return Err(ImportError::HashMismatch);
+        }
+        if let Some(notation) = notation {
+            // Дерево собирается на отдельной партии: запись проигрывается с начала.
+            let mut tree = core.start_position().map_err(ImportError::Settings)?;
+            tree.replay(notation).map_err(ImportError::Notation)?;
+            // `log` всегда доходит по главным продолжениям до листа дерева.
+            match tree.tree.find(&core.log) {
+                Some(node) if tree.tree.children(node).is_empty() => core.tree = tree.tree,
+                _ => return Err(ImportError::TreeMismatch),
+            }
+        }
+        // Лог проигрывается без часов, чтобы добавки не начислялись второй раз.
+        if let Some((control, clock, _)) = clock {
+            core.settings.time_control = Some(control);
+            core.clock = Some(clock);
+            // Идущая партия с часами играется только в конце лога.
+            if !core.is_finished() {
+                core.seek(core.log.len())
+                    .expect("moves that were just taken back can be made again");
+            }
+        }
+        Ok(core)
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    UnexpectedEnd,
+    UnknownVersion(String),
+    Malformed { line: usize, expected: &'static str },
+    Settings(CrossesError),
+    BadCursor(usize),
+    IllegalMove { number: usize, error: CrossesError },
+    PositionMismatch { x: usize, y: usize },
+    CounterMismatch,
+    HashMismatch,
+    Notation(NotationError),
+    TreeMismatch,
+}
+impl Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::UnexpectedEnd => write!(f, "save ends too early"),
+            ImportError::UnknownVersion(version) => {
+                write!(f, "unknown save version \"{version}\"")
+            }
+            ImportError::Malformed { line, expected } => {
+                write!(f, "line {line}: malformed \"{expected}\"")
+            }
+            ImportError::Settings(e) => write!(f, "{}", e),
+            ImportError::BadCursor(cursor) => {
+                write!(f, "cursor {cursor} points past the end of the log")
+            }
+            ImportError::IllegalMove { number, error } => {
+                write!(f, "move {number} is illegal: {error}")
+            }
+            ImportError::PositionMismatch { x, y } => {
+                write!(f, "cell x: {x} y: {y} doesn't match the log")
+            }
+            ImportError::CounterMismatch => write!(f, "counters don't match the log"),
+            ImportError::HashMismatch => write!(f, "position hash doesn't match the log"),
+            ImportError::Notation(e) => write!(f, "notation: {e}"),
+            ImportError::TreeMismatch => write!(f, "log isn't a line of the notation"),
+        }
+    }
+}
+impl Error for ImportError {}
+
+struct Lines<'a> {
+    inner: std::iter::Enumerate<std::str::Lines<'a>>,
+}
+impl<'a> Lines<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            inner: text.lines().enumerate(),
+        }
+    }
+    /// Следующая непустая строка и её номер (с единицы).
+    fn next(&mut self) -> Result<(usize, &'a str), ImportError> {
+        self.inner
+            .by_ref()
+            .map(|(index, text)| (index + 1, text.trim()))
+            .find(|(_, text)| !text.is_empty())
+            .ok_or(ImportError::UnexpectedEnd)
+    }
+    /// Следующая строка, которая обязана начинаться с `keyword`. Возвращает остаток строки.
+    fn keyword(&mut self, keyword: &'static str) -> Result<(usize, &'a str), ImportError> {
+        let (line, text) = self.next()?;
+        match text.strip_prefix(keyword) {
+            Some(rest) if rest.is_empty() || rest.starts_with(' ') => Ok((line, rest.trim())),
+            _ => Err(ImportError::Malformed {
+                line,
+                expected: keyword,
+            }),
+        }
+    }
+}
+
+fn number(line: usize, text: &str, expected: &'static str) -> Result<usize, ImportError> {
+    text.trim()
+        .parse()
+        .map_err(|_| ImportError::Malformed { line, expected })
+}
+
+fn numbers(line: usize, text: &str, expected: &'static str) -> Result<Vec<usize>, ImportError> {
+    text.split_whitespace()
+        .map(|token| number(line, token, expected))
+        .collect()
+}
+
+/// Выбывший по времени игрок и сколько ходов было сделано к тому моменту.
+type Flag = (usize, usize);
+
+/// Строка `clock` без ключевого слова: `-` или `запас+добавка`, время игроков
+/// и выбывшие по времени не позже `moves` ходов.
+fn clock(
+    line: usize,
+    text: &str,
+    players: usize,
+    moves: usize,
+) -> Result<Option<(TimeControl, Clock, Vec<Flag>)>, ImportError> {
+    let malformed = || ImportError::Malformed {
+        line,
+        expected: "clock",
+    };
+    let millis = |text| number(line, text, "clock").map(|ms| Duration::from_millis(ms as u64));
+    let text = text.trim();
+    if text == "-" {
+        return Ok(None);
+    }
+    let (control, remaining) = text.split_once(' ').ok_or_else(malformed)?;
+    let (total, increment) = control.split_once('+').ok_or_else(malformed)?;
+    let control = TimeControl {
+        total: millis(total)?,
+        increment: millis(increment)?,
+    };
+    let (remaining, flags): (Vec<_>, Vec<_>) = remaining
+        .split_whitespace()
+        .partition(|token| !token.contains('@'));
+    let remaining = remaining
+        .into_iter()
+        .map(millis)
+        .collect::<Result<Vec<_>, _>>()?;
+    if remaining.len() != players {
+        return Err(malformed());
+    }
+    let flags = flags
+        .into_iter()
+        .map(|token| {
+            let (player, at) = token.split_once('@').ok_or_else(malformed)?;
+            let player = player_char(player).filter(|&player| player < players);
+            let at = number(line, at, "clock")?;
+            match player {
+                Some(player) if at <= moves => Ok((player, at)),
+                _ => Err(malformed()),
+            }
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Some((control, Clock { remaining }, flags)))
+}
+
+/// Номер игрока по его букве из `board`.
+fn player_char(text: &str) -> Option<usize> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => PLAYER_CHARS.iter().position(|&player| player == c),
+        _ => None,
+    }
+}
+
+/// Закрашенная клетка расстановки: `x,y:игрок`.
+fn fill(line: usize, text: &str) -> Result<((usize, usize), usize), ImportError> {
+    let malformed = || ImportError::Malformed {
+        line,
+        expected: "fills",
+    };
+    let (index, player) = text.split_once(':').ok_or_else(malformed)?;
+    let player = player_char(player);
+    Ok((
+        pair(line, index, ',', "fills")?,
+        player.ok_or_else(malformed)?,
+    ))
+}
+
+fn pair(
+    line: usize,
+    text: &str,
+    separator: char,
+    expected: &'static str,
+) -> Result<(usize, usize), ImportError> {
+    let (first, second) = text
+        .trim()
+        .split_once(separator)
+        .ok_or(ImportError::Malformed { line, expected })?;
+    Ok((
+        number(line, first, expected)?,
+        number(line, second, expected)?,
+    ))
+}
+
+pub fn cell_to_char(cell: SampleCell) -> char {
+    match cell.kind() {
+        CellKind::Border => '#',
+        CellKind::Empty => '.',
+        CellKind::Cross => PLAYER_CHARS[cell.player()],
+        CellKind::Filled => PLAYER_CHARS[cell.player()].to_ascii_uppercase(),
+    }
+}
+
+fn is_cell_char(c: char) -> bool {
+    matches!(c, '#' | '.') || PLAYER_CHARS.contains(&c.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Партия, в которой сделано `moves` первых допустимых ходов.
+    fn played(moves: usize) -> CrossesCore {
+        let mut game = CrossesCore::default();
+        game.play_first_moves(moves);
+        game
+    }
+
+    /// Заменяет строку с ключевым словом `keyword` на `line` или убирает её.
+    fn edit(text: &str, keyword: &str, line: Option<&str>) -> String {
+        text.lines()
+            .filter_map(|text| match text.split_whitespace().next() {
+                Some(word) if word == keyword => line.map(str::to_string),
+                _ => Some(text.to_string()),
+            })
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut game = played(10);
+        game.back().unwrap();
+        game.back().unwrap();
+        let text = game.export();
+
+        let loaded = CrossesCore::import(&text).unwrap();
+        assert_eq!(loaded.log, game.log);
+        assert_eq!(loaded.player_manager.current_move(), 8);
+        assert_eq!(loaded.board_manager.hash, game.board_manager.hash);
+        assert_eq!(loaded.export(), text);
+    }
+
+    #[test]
+    fn old_versions() {
+        let text = played(10).export();
+        let v5 = edit(&text, "notation", None);
+        let v4 = edit(&v5, "clock", None);
+        let v3 = edit(&edit(&v4, "fills", None), "obstacles", None);
+        let v2 = edit(&v3, "hash", None);
+        let v1 = edit(&v2, "start", None);
+        for (version, old) in [(5, v5), (4, v4), (3, v3), (2, v2), (1, v1)] {
+            // Со слова "crosses" начинается и строка счётчиков, поэтому версия
+            // меняется только в первой строке.
+            let (_, rest) = old.split_once('\n').unwrap();
+            let old = format!("crosses {version}\n{rest}");
+            let loaded =
+                CrossesCore::import(&old).unwrap_or_else(|e| panic!("version {version}: {e}"));
+            assert_eq!(loaded.export(), text, "version {version}");
+        }
+    }
+
+    #[test]
+    fn layout_round_trip() {
+        let mut game = CrossesCore::new(GameSettings {
+            layout: Layout {
+                fills: vec![((1, 0), 0), ((8, 9), 1), ((4, 6), 1)],
+                obstacles: vec![(5, 5), (0, 9)],
+                ..Layout::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        game.play_first_moves(6);
+        let text = game.export();
+        assert!(text.contains("\nfills 1,0:b 8,9:r 4,6:r\n"));
+        assert!(text.contains("\nobstacles 5,5 0,9\n"));
+
+        let v4 = edit(&edit(&text, "notation", None), "clock", None);
+        let (_, rest) = v4.split_once('\n').unwrap();
+        for text in [text.clone(), format!("crosses 4\n{rest}")] {
+            let loaded = CrossesCore::import(&text).unwrap();
+            assert_eq!(loaded.settings.layout, game.settings.layout);
+            assert_eq!(loaded.board_manager.hash, game.board_manager.hash);
+            assert_eq!(loaded.export(), game.export());
+        }
+    }
+
+    #[test]
+    fn variations_survive_round_trip() {
+        let mut game = played(10);
+        let main = game.log.clone();
+        game.seek(5).unwrap();
+        let (x, y) = game.legal_moves()[1].index;
+        game.make_move(x, y).unwrap();
+        let variation = game.log.clone();
+        game.seek(4).unwrap();
+
+        let loaded = CrossesCore::import(&game.export()).unwrap();
+        assert_eq!(loaded.log, variation);
+        assert_eq!(loaded.player_manager.current_move(), 4);
+        assert!(loaded.tree.find(&main).is_some());
+        assert!(!loaded.is_main_line());
+        assert_eq!(loaded.export(), game.export());
+    }
+
+    #[test]
+    fn unplayable_variations_are_left_out() {
+        let mut game = played(3);
+        // Стартовая клетка занята с самого начала, такой вариант не проигрывается.
+        let start = game.settings.layout.starts[0];
+        game.tree.insert(&[start]);
+        assert!(game.notation().is_err());
+
+        let loaded = CrossesCore::import(&game.export()).unwrap();
+        assert_eq!(loaded.log, game.log);
+        assert!(loaded.tree.find(&[start]).is_none());
+    }
+
+    #[test]
+    fn rejects_log_outside_the_notation() {
+        let game = played(3);
+        let text = edit(&game.export(), "notation", Some("notation 1. b1"));
+        assert!(matches!(
+            CrossesCore::import(&text),
+            Err(ImportError::TreeMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_corrupted_board() {
+        let text = played(10).export();
+        let mut lines: Vec<&str> = text.lines().collect();
+        let row = lines.iter().position(|&line| line == "board").unwrap() + 1;
+        let corrupted = match lines[row].chars().next() {
+            Some('.') => format!("r{}", &lines[row][1..]),
+            _ => format!(".{}", &lines[row][1..]),
+        };
+        lines[row] = &corrupted;
+        assert!(matches!(
+            CrossesCore::import(&lines.join("\n")),
+            Err(ImportError::PositionMismatch { x: 0, y: 0 })
+        ));
+    }
+
+    #[test]
+    fn rejects_corrupted_counters() {
+        let game = played(10);
+        let moves = &game.board_manager.moves_counter;
+        let line = format!("moves {} {}", moves[0] + 1, moves[1]);
+        let text = edit(&game.export(), "moves", Some(&line));
+        assert!(matches!(
+            CrossesCore::import(&text),
+            Err(ImportError::CounterMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_corrupted_hash() {
+        let game = played(10);
+        let line = format!("hash {:016x}", !game.board_manager.hash);
+        let text = edit(&game.export(), "hash", Some(&line));
+        assert!(matches!(
+            CrossesCore::import(&text),
+            Err(ImportError::HashMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_cursor_past_the_log() {
+        let text = edit(&played(3).export(), "cursor", Some("cursor 4"));
+        assert!(matches!(
+            CrossesCore::import(&text),
+            Err(ImportError::BadCursor(4))
+        ));
+    }
+}
@@ -0,0 +1,335 @@
+//! Проверка доски: всё, что `make_move` и `cancel_move` поддерживают
+//! по ходу игры, считается заново по типам и владельцам клеток и
+//! сравнивается с тем, что лежит в клетках и счётчиках.
+//!
+//! Что считается правильным:
+//! * игрок дотягивается до пустой клетки или чужого крестика, если рядом есть
+//!   его крестик или живая закрашенная клетка; активность — число таких соседей,
+//!   но не больше 3, а клетка перегрета, если хоть у кого-то их 3 и больше;
+//! * группа закрашенных клеток живая целиком, если касается крестика своего
+//!   игрока, и мёртвая целиком, если нет;
+//! * крестик важен, если касается закрашенной клетки своего игрока, — иначе
+//!   закрашивание последнего такого крестика не убьёт группу;
+//! * счётчик ходов равен числу клеток, до которых игрок дотягивается по
+//!   посчитанной заново активности, а счётчик крестиков — числу крестиков;
+//! * хеш равен посчитанному заново.
+//!
+//! По той же модели `rebuild` собирает всё это с нуля, когда позицию
+//! не получить ходами, например для расстановки с закрашенными клетками.
+use std::fmt::Display;
+
+use crosses_core::board_manager::{BoardManager, Cell, CellKind};
+
+use super::sample_board_manager::SampleBoardManager;
+
+/// Наибольшая активность, которая помещается в два бита.
+const MAX_ACTIVITY: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Discrepancy {
+    Activity {
+        index: (usize, usize),
+        player: usize,
+        expected: u8,
+        found: u8,
+    },
+    Overheat {
+        index: (usize, usize),
+        expected: bool,
+    },
+    Alive {
+        index: (usize, usize),
+        expected: bool,
+    },
+    Important {
+        index: (usize, usize),
+        expected: bool,
+    },
+    MovesCounter {
+        player: usize,
+        expected: usize,
+        found: usize,
+    },
+    CrossesCounter {
+        player: usize,
+        expected: usize,
+        found: usize,
+    },
+    Hash {
+        expected: u64,
+        found: u64,
+    },
+}
+impl Display for Discrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Discrepancy::Activity {
+                index: (x, y),
+                player,
+                expected,
+                found,
+            } => write!(
+                f,
+                "cell x: {x} y: {y}: activity of player {player} is {found}, expected {expected}"
+            ),
+            Discrepancy::Overheat {
+                index: (x, y),
+                expected,
+            } => write!(
+                f,
+                "cell x: {x} y: {y}: overheat is {}, expected {expected}",
+                !expected
+            ),
+            Discrepancy::Alive {
+                index: (x, y),
+                expected,
+            } => write!(
+                f,
+                "cell x: {x} y: {y}: alive is {}, expected {expected}",
+                !expected
+            ),
+            Discrepancy::Important {
+                index: (x, y),
+                expected,
+            } => write!(
+                f,
+                "cell x: {x} y: {y}: important is {}, expected {expected}",
+                !expected
+            ),
+            Discrepancy::MovesCounter {
+                player,
+                expected,
+                found,
+            } => write!(
+                f,
+                "moves counter of player {player} is {found}, expected {expected}"
+            ),
+            Discrepancy::CrossesCounter {
+                player,
+                expected,
+                found,
+            } => write!(
+                f,
+                "crosses counter of player {player} is {found}, expected {expected}"
+            ),
+            Discrepancy::Hash { expected, found } => {
+                write!(f, "hash is {found:016x}, expected {expected:016x}")
+            }
+        }
+    }
+}
+
+impl SampleBoardManager {
+    /// Все расхождения с состоянием, посчитанным с нуля. Пусто, если доска в порядке.
+    pub fn validate(&self) -> Vec<Discrepancy> {
+        let mut found = Vec::new();
+        let players = self.players();
+        let alive = self.expected_alive();
+        let mut crosses = vec![0; players];
+        let mut moves = vec![0; players];
+        for y in 0..self.max_y {
+            for x in 0..self.max_x {
+                let index = (x, y);
+                let cell = self.get(index);
+                match cell.kind() {
+                    CellKind::Empty | CellKind::Cross => {
+                        let own = (cell.kind() == CellKind::Cross).then(|| cell.player());
+                        if let Some(owner) = own {
+                            crosses[owner] += 1;
+                            let expected = self.expected_important(index, owner);
+                            if cell.is_important() != expected {
+                                found.push(Discrepancy::Important { index, expected });
+                            }
+                        }
+                        let mut overheat = false;
+                        for (player, moves) in moves.iter_mut().enumerate() {
+                            let sources = self.sources(index, player, &alive);
+                            if sources > 0 && own != Some(player) {
+                                *moves += 1;
+                            }
+                            overheat |= sources >= MAX_ACTIVITY as usize;
+                            let expected = sources.min(MAX_ACTIVITY as usize) as u8;
+                            if cell.activity(player) != expected {
+                                found.push(Discrepancy::Activity {
+                                    index,
+                                    player,
+                                    expected,
+                                    found: cell.activity(player),
+                                });
+                            }
+                        }
+                        if cell.is_overheated() != overheat {
+                            found.push(Discrepancy::Overheat {
+                                index,
+                                expected: overheat,
+                            });
+                        }
+                    }
+                    CellKind::Filled => {
+                        let expected = alive[y * self.max_x + x];
+                        if cell.is_alive() != expected {
+                            found.push(Discrepancy::Alive { index, expected });
+                        }
+                    }
+                    CellKind::Border => {}
+                }
+            }
+        }
+        for (player, (&crosses, &expected)) in crosses.iter().zip(&moves).enumerate() {
+            if self.moves_counter[player] != expected {
+                found.push(Discrepancy::MovesCounter {
+                    player,
+                    expected,
+                    found: self.moves_counter[player],
+                });
+            }
+            if self.crosses_counter[player] != crosses {
+                found.push(Discrepancy::CrossesCounter {
+                    player,
+                    expected: crosses,
+                    found: self.crosses_counter[player],
+                });
+            }
+        }
+        let expected = self.full_hash();
+        if self.hash != expected {
+            found.push(Discrepancy::Hash {
+                expected,
+                found: self.hash,
+            });
+        }
+        found
+    }
+    /// Пересчитывает активность, перегрев, живость, важность крестиков и счётчики
+    /// по типам и владельцам клеток. Важными становятся все крестики, которые
+    /// касаются закрашенных клеток своего игрока. Хеш не трогает.
+    pub fn rebuild(&mut self) {
+        let players = self.players();
+        let alive = self.expected_alive();
+        let mut crosses = vec![0; players];
+        for y in 0..self.max_y {
+            for x in 0..self.max_x {
+                let index = (x, y);
+                let offset = y * self.max_x + x;
+                let mut cell = self.board[offset];
+                match cell.kind() {
+                    CellKind::Empty | CellKind::Cross => {
+                        cell.reset_activity();
+                        let mut overheat = false;
+                        for player in 0..players {
+                            let sources = self.sources(index, player, &alive);
+                            overheat |= sources >= MAX_ACTIVITY as usize;
+                            for _ in 0..sources.min(MAX_ACTIVITY as usize) {
+                                cell.activate(player);
+                            }
+                        }
+                        cell.set_overheat(overheat);
+                        if cell.kind() == CellKind::Cross {
+                            crosses[cell.player()] += 1;
+                            cell.set_important(self.expected_important(index, cell.player()));
+                        }
+                    }
+                    CellKind::Filled => cell.set_alive(alive[offset]),
+                    CellKind::Border => {}
+                }
+                self.board[offset] = cell;
+            }
+        }
+        self.crosses_counter = crosses;
+        self.moves_counter = (0..players)
+            .map(|player| self.legal_moves(player).len())
+            .collect();
+    }
+    /// Важен ли крестик `player` в `index`: касается ли он закрашенной клетки своего игрока.
+    fn expected_important(&self, index: (usize, usize), player: usize) -> bool {
+        self.adjacent(index).into_iter().any(|near| {
+            let near = self.get(near);
+            near.kind() == CellKind::Filled && near.player() == player
+        })
+    }
+    /// Сколько соседей `index` дают `player` до него дотянуться.
+    fn sources(&self, index: (usize, usize), player: usize, alive: &[bool]) -> usize {
+        self.adjacent(index)
+            .into_iter()
+            .filter(|&(near_x, near_y)| {
+                let near = self.get((near_x, near_y));
+                match near.kind() {
+                    CellKind::Cross => near.player() == player,
+                    CellKind::Filled => {
+                        near.player() == player && alive[near_y * self.max_x + near_x]
+                    }
+                    CellKind::Empty | CellKind::Border => false,
+                }
+            })
+            .count()
+    }
+    /// Живость каждой закрашенной клетки, посчитанная по её группе.
+    fn expected_alive(&self) -> Vec<bool> {
+        let mut alive = vec![false; self.board.len()];
+        let mut grouped = vec![false; self.board.len()];
+        for y in 0..self.max_y {
+            for x in 0..self.max_x {
+                let cell = self.get((x, y));
+                if cell.kind() != CellKind::Filled || grouped[y * self.max_x + x] {
+                    continue;
+                }
+                let group = self.filled_group((x, y));
+                let touches_cross = group
+                    .iter()
+                    .flat_map(|&index| self.adjacent(index))
+                    .map(|near| self.get(near))
+                    .any(|near| near.kind() == CellKind::Cross && near.player() == cell.player());
+                for &(x, y) in &group {
+                    grouped[y * self.max_x + x] = true;
+                    alive[y * self.max_x + x] = touches_cross;
+                }
+            }
+        }
+        alive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sample_core::layout::Layout;
+
+    /// Расстановка с живыми и мёртвой закрашенными клетками и препятствием.
+    fn board() -> SampleBoardManager {
+        let layout = Layout {
+            fills: vec![((1, 0), 0), ((2, 0), 0), ((8, 9), 1), ((5, 6), 1)],
+            obstacles: vec![(5, 5)],
+            ..Layout::default()
+        };
+        SampleBoardManager::new(10, 10, &layout).unwrap()
+    }
+
+    #[test]
+    fn rebuilt_layout_passes_validation() {
+        let board = board();
+        assert_eq!(board.validate(), []);
+        assert!(board.get((1, 0)).is_alive());
+        assert!(!board.get((5, 6)).is_alive());
+        assert!(board.get((0, 0)).is_important());
+        assert_eq!(board.moves_counter[0], board.legal_moves(0).len());
+    }
+
+    #[test]
+    fn validation_finds_what_rebuild_repairs() {
+        let mut board = board();
+        board.moves_counter[1] += 1;
+        board.board[0].set_important(false);
+        let found = board.validate();
+        assert!(found.contains(&Discrepancy::Important {
+            index: (0, 0),
+            expected: true,
+        }));
+        assert!(found
+            .iter()
+            .any(|d| matches!(d, Discrepancy::MovesCounter { player: 1, .. })));
+
+        board.rebuild();
+        board.rehash();
+        assert_eq!(board.validate(), []);
+    }
+}
@@ -0,0 +1,288 @@
+//! Random legal games on `SampleBoardManager`: every `cancel_move` has to
+//! bring back exactly the board that was there before the move, and the
+//! counters, aliveness and importance have to agree with the cells after
+//! every step.
+
+use crosses_core::board_manager::{ActivationStatus, BoardManager, Cell, CellKind};
+use crosses_egui::sample_core::{
+    layout::{Layout, Preset},
+    sample_board_manager::{SampleBoardManager, MAX_PLAYERS},
+    sample_cell::SampleCell,
+};
+
+/// xorshift64*, seeded so that a failure can be replayed.
+struct Rng(u64);
+impl Rng {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) % bound as u64) as usize
+    }
+}
+
+/// Everything a move may change, with the bookkeeping flags left out.
+#[derive(Debug, PartialEq, Eq)]
+struct Snapshot {
+    cells: Vec<(u8, u8, bool)>,
+    moves_counter: Vec<usize>,
+    crosses_counter: Vec<usize>,
+    hash: u64,
+}
+impl Snapshot {
+    fn new(board: &SampleBoardManager) -> Self {
+        Self {
+            cells: board
+                .board
+                .iter()
+                .map(|cell| (cell.state(), cell.activity, cell.is_overheated()))
+                .collect(),
+            moves_counter: board.moves_counter.clone(),
+            crosses_counter: board.crosses_counter.clone(),
+            hash: board.hash,
+        }
+    }
+}
+
+fn cancel(board: &mut SampleBoardManager, index: (usize, usize)) {
+    let origin = board.get(index).origin();
+    board
+        .cancel_move(index, || origin)
+        .unwrap_or_else(|e| panic!("can't cancel {index:?}: {e}"));
+}
+
+/// Filled cells of `player` connected to `start`, the same way `traverse` walks them.
+fn group(board: &SampleBoardManager, start: (usize, usize)) -> Vec<(usize, usize)> {
+    let player = board.get(start).player();
+    let mut group = vec![start];
+    let mut next = 0;
+    while let Some(&index) = group.get(next) {
+        next += 1;
+        for adjacent in board.adjacent(index) {
+            let cell = board.get(adjacent);
+            if cell.kind() == CellKind::Filled
+                && cell.player() == player
+                && !group.contains(&adjacent)
+            {
+                group.push(adjacent);
+            }
+        }
+    }
+    group
+}
+
+fn check_invariants(board: &SampleBoardManager, context: &str) {
+    let discrepancies = board.validate();
+    assert!(discrepancies.is_empty(), "{context}: {discrepancies:?}");
+    assert_eq!(board.hash, board.full_hash(), "{context}: hash drifted");
+    for player in 0..board.players() {
+        assert_eq!(
+            board.moves_counter[player],
+            board.legal_moves(player).len(),
+            "{context}: moves counter of player {player}"
+        );
+        let crosses = board
+            .board
+            .iter()
+            .filter(|cell| cell.kind() == CellKind::Cross && cell.player() == player)
+            .count();
+        assert_eq!(
+            board.crosses_counter[player], crosses,
+            "{context}: crosses counter of player {player}"
+        );
+    }
+    for y in 0..board.max_y {
+        for x in 0..board.max_x {
+            let cell = board.get((x, y));
+            if cell.kind() != CellKind::Filled {
+                continue;
+            }
+            let group = group(board, (x, y));
+            let alive = cell.is_alive();
+            assert!(
+                group
+                    .iter()
+                    .all(|&index| board.get(index).is_alive() == alive),
+                "{context}: group at {x},{y} is partly alive"
+            );
+            let own_crosses: Vec<_> = group
+                .iter()
+                .flat_map(|&index| board.adjacent(index))
+                .map(|index| board.get(index))
+                .filter(|near| near.kind() == CellKind::Cross && near.player() == cell.player())
+                .collect();
+            assert_eq!(
+                alive,
+                !own_crosses.is_empty(),
+                "{context}: group at {x},{y} is alive only if it touches its own cross"
+            );
+            assert!(
+                !alive || own_crosses.iter().any(|cross| cross.is_important()),
+                "{context}: alive group at {x},{y} relies on no important cross"
+            );
+        }
+    }
+}
+
+/// Plays until nobody can move or `limit` moves are made. Every move is
+/// cancelled and redone on the spot, and at the end the whole game is
+/// unwound back to the start.
+fn play(seed: u64, max_x: usize, max_y: usize, players: usize, limit: usize) {
+    let layout = Layout::preset(Preset::Corners, max_x, max_y, players);
+    play_from(seed, max_x, max_y, &layout, limit);
+}
+
+fn play_from(seed: u64, max_x: usize, max_y: usize, layout: &Layout, limit: usize) {
+    let mut rng = Rng(seed);
+    let players = layout.players();
+    let mut board = SampleBoardManager::new(max_x, max_y, layout).unwrap();
+    check_invariants(&board, &format!("seed {seed}, start"));
+    let mut history = Vec::new();
+    let mut player = 0;
+    for number in 0..limit {
+        let moves = board.legal_moves(player);
+        if moves.is_empty() {
+            break;
+        }
+        let index = moves[rng.below(moves.len())].index;
+        let context = format!("seed {seed}, move {number} at {index:?} by {player}");
+        let before = Snapshot::new(&board);
+        board.make_move(index, player).unwrap();
+        check_invariants(&board, &context);
+        let after = Snapshot::new(&board);
+
+        cancel(&mut board, index);
+        assert_eq!(Snapshot::new(&board), before, "{context}: cancel");
+        board.make_move(index, player).unwrap();
+        assert_eq!(Snapshot::new(&board), after, "{context}: redo");
+
+        history.push((index, before));
+        // Players move several times in a row, like in a real game.
+        if rng.below(3) == 0 {
+            player = (player + 1) % players;
+        }
+    }
+    while let Some((index, before)) = history.pop() {
+        cancel(&mut board, index);
+        assert_eq!(
+            Snapshot::new(&board),
+            before,
+            "seed {seed}: unwinding move {} at {index:?}",
+            history.len()
+        );
+    }
+}
+
+#[test]
+fn random_games_two_players() {
+    for seed in 1..=40 {
+        play(seed, 8, 8, 2, 200);
+    }
+}
+
+#[test]
+fn random_games_more_players() {
+    for seed in 1..=20 {
+        play(seed, 9, 7, 3, 200);
+        play(seed, 10, 10, MAX_PLAYERS, 300);
+    }
+}
+
+#[test]
+fn random_games_small_boards() {
+    for seed in 1..=40 {
+        play(seed, 2, 2, 2, 10);
+        play(seed, 3, 2, 2, 20);
+    }
+}
+
+#[test]
+fn random_games_from_presets() {
+    for seed in 1..=10 {
+        for preset in Preset::ALL {
+            for players in 2..=MAX_PLAYERS {
+                play_from(seed, 9, 8, &Layout::preset(preset, 9, 8, players), 200);
+            }
+        }
+    }
+}
+
+#[test]
+fn random_games_with_fills_and_obstacles() {
+    let layout = Layout {
+        starts: vec![(1, 1), (6, 6), (6, 1)],
+        fills: vec![
+            ((2, 2), 0),
+            ((3, 3), 0),
+            ((4, 4), 1),
+            ((0, 6), 2),
+            ((5, 5), 1),
+        ],
+        obstacles: vec![(3, 0), (3, 1), (4, 6), (4, 7), (0, 4)],
+    };
+    for seed in 1..=20 {
+        play_from(seed, 8, 8, &layout, 200);
+    }
+}
+
+#[test]
+fn layout_cells_must_not_overlap() {
+    let mut layout = Layout::preset(Preset::Corners, 4, 4, 2);
+    layout.obstacles.push((0, 0));
+    assert!(SampleBoardManager::new(4, 4, &layout).is_err());
+    layout.obstacles = vec![(4, 0)];
+    assert!(SampleBoardManager::new(4, 4, &layout).is_err());
+    layout.obstacles.clear();
+    layout.fills.push(((1, 1), 2));
+    assert!(SampleBoardManager::new(4, 4, &layout).is_err());
+}
+
+#[test]
+fn filling_takes_the_cross_from_its_owner() {
+    let layout = Layout {
+        starts: vec![(0, 0), (1, 1)],
+        fills: Vec::new(),
+        obstacles: Vec::new(),
+    };
+    let mut board = SampleBoardManager::new(2, 2, &layout).unwrap();
+    let before = Snapshot::new(&board);
+    board.make_move((1, 1), 0).unwrap();
+    assert_eq!(board.crosses_counter, [1, 0]);
+    check_invariants(&board, "fill");
+    cancel(&mut board, (1, 1));
+    assert_eq!(Snapshot::new(&board), before);
+}
+
+#[test]
+fn activity_stays_in_two_bits() {
+    use ActivationStatus::*;
+    for player in 0..MAX_PLAYERS {
+        let mut cell = SampleCell::EMPTY;
+        let others = |cell: SampleCell| {
+            (0..MAX_PLAYERS)
+                .filter(|&other| other != player)
+                .map(|other| cell.activity(other))
+                .collect::<Vec<_>>()
+        };
+        // The other players' bits are set to something that must survive.
+        for other in (0..MAX_PLAYERS).filter(|&other| other != player) {
+            for _ in 0..=other % 3 {
+                cell.activate(other);
+            }
+        }
+        let neighbours = others(cell);
+
+        let statuses: Vec<_> = (0..5).map(|_| cell.activate(player)).collect();
+        assert!(matches!(
+            statuses[..],
+            [Regular, Regular, Overheat, Overheat, Overheat]
+        ));
+        assert_eq!(cell.activity(player), 3, "player {player}");
+        assert_eq!(others(cell), neighbours, "activating player {player}");
+
+        let statuses: Vec<_> = (0..5).map(|_| cell.deactivate(player)).collect();
+        assert!(matches!(statuses[..], [Regular, Regular, Zero, Zero, Zero]));
+        assert_eq!(cell.activity(player), 0, "player {player}");
+        assert_eq!(others(cell), neighbours, "deactivating player {player}");
+    }
+}
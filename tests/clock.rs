@@ -0,0 +1,163 @@
+//! Time controls: the clock runs for the player to move, gains the increment
+//! when the turn passes, knocks out the player whose time runs out and is
+//! saved with the game.
+
+use std::time::Duration;
+
+use common::finish_turn;
+use crosses_egui::sample_core::{
+    clock::TimeControl,
+    layout::{Layout, Preset},
+    CrossesCore, CrossesError, GameSettings, LoseReason,
+};
+
+mod common;
+
+const CONTROL: TimeControl = TimeControl {
+    total: Duration::from_secs(60),
+    increment: Duration::from_secs(2),
+};
+
+fn timed_game() -> CrossesCore {
+    CrossesCore::new(GameSettings {
+        time_control: Some(CONTROL),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+fn three_player_game() -> CrossesCore {
+    CrossesCore::new(GameSettings {
+        time_control: Some(CONTROL),
+        layout: Layout::preset(Preset::Corners, 10, 10, 3),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+fn remaining(game: &CrossesCore) -> Vec<Duration> {
+    game.clock.as_ref().unwrap().remaining.clone()
+}
+
+#[test]
+fn untimed_games_have_no_clock() {
+    let mut game = CrossesCore::default();
+    game.tick(Duration::from_secs(3600));
+    assert!(game.clock.is_none());
+    assert!(game.flags.is_empty());
+    assert!(!game.is_over());
+}
+
+#[test]
+fn clock_runs_for_the_player_to_move() {
+    let mut game = timed_game();
+    game.tick(Duration::from_secs(10));
+    assert_eq!(
+        remaining(&game),
+        [Duration::from_secs(50), Duration::from_secs(60)]
+    );
+
+    // The increment comes once per turn, not once per move.
+    finish_turn(&mut game);
+    assert_eq!(
+        remaining(&game),
+        [Duration::from_secs(52), Duration::from_secs(60)]
+    );
+    game.tick(Duration::from_secs(5));
+    assert_eq!(
+        remaining(&game),
+        [Duration::from_secs(52), Duration::from_secs(55)]
+    );
+
+    // A running game can't be looked back over, so nothing stops its clock.
+    assert!(matches!(game.back(), Err(CrossesError::ClockRunning)));
+    assert!(matches!(game.seek(0), Err(CrossesError::ClockRunning)));
+    game.tick(Duration::from_secs(5));
+    assert_eq!(
+        remaining(&game),
+        [Duration::from_secs(52), Duration::from_secs(50)]
+    );
+}
+
+#[test]
+fn flag_fall_loses_the_game() {
+    let mut game = timed_game();
+    finish_turn(&mut game);
+    game.tick(Duration::from_secs(61));
+    assert_eq!(game.flags, [(1, 4)]);
+    assert!(game.is_over());
+    assert_eq!(game.lose_reason(1), Some(LoseReason::OutOfTime));
+    assert_eq!(game.lose_reason(0), None);
+    assert!(game.legal_moves().is_empty());
+    assert!(matches!(
+        game.make_move(0, 0),
+        Err(CrossesError::PlayerError(_))
+    ));
+
+    // Once over, the clock no longer runs and the game can be looked back over.
+    let before = remaining(&game);
+    game.tick(Duration::from_secs(1));
+    assert_eq!(remaining(&game), before);
+    game.seek(0).unwrap();
+    assert!(!game.is_over());
+    assert!(game.is_finished());
+    game.tick(Duration::from_secs(1));
+    assert_eq!(remaining(&game), before);
+}
+
+#[test]
+fn spending_time_knocks_out_nobody() {
+    let mut game = timed_game();
+    assert_eq!(game.spend_time(Duration::from_secs(10)), None);
+    assert_eq!(game.spend_time(Duration::from_secs(61)), Some(0));
+    assert_eq!(remaining(&game)[0], Duration::ZERO);
+    assert!(game.flags.is_empty());
+    assert!(!game.is_over());
+    assert_eq!(game.player_manager.current_player(), 0);
+}
+
+#[test]
+fn flag_fall_knocks_out_only_that_player() {
+    let mut game = three_player_game();
+    game.tick(Duration::from_secs(61));
+    assert_eq!(game.flags, [(0, 0)]);
+    assert!(!game.is_over());
+    assert_eq!(game.lose_reason(0), Some(LoseReason::OutOfTime));
+    assert_eq!(game.player_manager.current_player(), 1);
+
+    // The others keep playing until the last but one runs out of time too.
+    finish_turn(&mut game);
+    assert_eq!(game.player_manager.current_player(), 2);
+    game.tick(Duration::from_secs(61));
+    assert!(game.is_over());
+    let end = game.player_manager.current_move();
+    assert_eq!(game.flags, [(0, 0), (2, end)]);
+
+    // Looking back over the flags keeps them.
+    game.seek(0).unwrap();
+    assert_eq!(game.player_manager.current_player(), 1);
+    game.back().unwrap_err();
+    game.seek(end).unwrap();
+    assert!(game.is_over());
+
+    let loaded = CrossesCore::import(&game.export()).unwrap();
+    assert_eq!(loaded.flags, game.flags);
+    assert!(loaded.is_over());
+    assert_eq!(loaded.export(), game.export());
+}
+
+#[test]
+fn clock_is_saved_with_the_game() {
+    let mut game = timed_game();
+    game.tick(Duration::from_millis(12_345));
+    finish_turn(&mut game);
+    game.tick(Duration::from_millis(500));
+
+    let loaded = CrossesCore::import(&game.export()).unwrap();
+    assert_eq!(loaded.settings.time_control, Some(CONTROL));
+    assert_eq!(loaded.clock, game.clock);
+    assert_eq!(loaded.export(), game.export());
+
+    let untimed = CrossesCore::import(&CrossesCore::default().export()).unwrap();
+    assert!(untimed.clock.is_none());
+}
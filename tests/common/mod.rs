@@ -0,0 +1,19 @@
+//! Moves shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use crosses_egui::sample_core::CrossesCore;
+
+/// Makes the first legal move and returns its cell.
+pub fn play_first_move(game: &mut CrossesCore) -> (usize, usize) {
+    let index = game.legal_moves()[0].index;
+    game.make_move(index.0, index.1).unwrap();
+    index
+}
+
+/// Makes the first legal moves until the turn passes.
+pub fn finish_turn(game: &mut CrossesCore) {
+    let player = game.player_manager.current_player();
+    while game.player_manager.current_player() == player {
+        play_first_move(game);
+    }
+}
@@ -0,0 +1,291 @@
+//! The network protocol on its own, and a host and a guest talking over
+//! localhost the way two instances of the app do.
+
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use common::play_first_move;
+use crosses_egui::network::{
+    Guest, Host, Message, NetworkError, Notice, MAX_LINE_LEN, PROTOCOL_VERSION,
+};
+use crosses_egui::sample_core::{clock::TimeControl, CrossesCore, GameSettings};
+
+mod common;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn messages_survive_a_round_trip() {
+    let messages = [
+        Message::Hello {
+            version: PROTOCOL_VERSION,
+            watch: false,
+        },
+        Message::Hello {
+            version: PROTOCOL_VERSION,
+            watch: true,
+        },
+        Message::Welcome {
+            version: PROTOCOL_VERSION,
+            seat: Some(1),
+        },
+        Message::Welcome {
+            version: PROTOCOL_VERSION,
+            seat: None,
+        },
+        Message::Move((12, 3)),
+        Message::Clock {
+            remaining: vec![Duration::from_millis(59_500), Duration::ZERO],
+            flags: vec![(1, 7)],
+        },
+        Message::Clock {
+            remaining: vec![Duration::from_secs(60); 3],
+            flags: Vec::new(),
+        },
+        Message::Rejected("cell is out of reach".to_string()),
+        Message::Sync,
+        Message::Game("crosses 4\nsize 2x2\n".to_string()),
+        Message::Bye("host closed the game".to_string()),
+    ];
+    let stream: String = messages.iter().map(Message::encode).collect();
+    let mut reader = Cursor::new(stream);
+    for message in messages {
+        assert_eq!(Message::read(&mut reader).unwrap(), Some(message));
+    }
+    assert_eq!(Message::read(&mut reader).unwrap(), None);
+}
+
+#[test]
+fn malformed_messages_are_errors() {
+    for line in [
+        "move 1",
+        "move a,b",
+        "welcome 1",
+        "hello",
+        "hello 2 play",
+        "clock 1.5",
+        "clock 100 1@",
+        "game 2\nonly one",
+        "what",
+    ] {
+        let mut reader = Cursor::new(format!("{line}\n"));
+        assert!(
+            matches!(Message::read(&mut reader), Err(NetworkError::Malformed(_))),
+            "{line:?}"
+        );
+    }
+}
+
+#[test]
+fn endless_lines_are_cut_off() {
+    let mut reader = Cursor::new(format!("rejected {}", "x".repeat(MAX_LINE_LEN)));
+    assert!(matches!(
+        Message::read(&mut reader),
+        Err(NetworkError::TooLong)
+    ));
+
+    // A long line that does end is read whole, a game's notation can be long.
+    let reason = "x".repeat(MAX_LINE_LEN - "bye \n".len());
+    let mut reader = Cursor::new(format!("bye {reason}\n"));
+    assert_eq!(
+        Message::read(&mut reader).unwrap(),
+        Some(Message::Bye(reason))
+    );
+}
+
+/// A host and its guests, each with its own copy of the game.
+struct Table {
+    /// `None` while the host is away.
+    host: Option<Host>,
+    host_game: CrossesCore,
+    /// In the order they joined. The first one plays for player 1.
+    guests: Vec<(Guest, CrossesCore)>,
+}
+impl Table {
+    fn new() -> Self {
+        let host_game = CrossesCore::default();
+        let host = Host::start(0, vec![1], &host_game, Arc::new(|| {})).unwrap();
+        let mut table = Self {
+            host: Some(host),
+            host_game,
+            guests: Vec::new(),
+        };
+        table.join(false);
+        table
+    }
+    /// Connects another guest and waits until it has the game. Returns its index.
+    fn join(&mut self, watch: bool) -> usize {
+        let address = format!("127.0.0.1:{}", self.host().port());
+        let guest = Guest::connect(address, watch, Arc::new(|| {}));
+        self.guests.push((guest, CrossesCore::default()));
+        let joined = self.guests.len() - 1;
+        self.exchange(|table, _| table.guests[joined].0.live().is_some());
+        joined
+    }
+    /// Updates every side until `done` holds, collecting each guest's notices.
+    fn exchange(
+        &mut self,
+        mut done: impl FnMut(&Self, &[Vec<Notice>]) -> bool,
+    ) -> Vec<Vec<Notice>> {
+        let start = Instant::now();
+        let mut notices: Vec<Vec<Notice>> = self.guests.iter().map(|_| Vec::new()).collect();
+        while !done(self, &notices) {
+            assert!(
+                start.elapsed() < TIMEOUT,
+                "timed out, guests saw {notices:?}"
+            );
+            if let Some(host) = &mut self.host {
+                host.update(&mut self.host_game);
+            }
+            for ((guest, game), notices) in self.guests.iter_mut().zip(&mut notices) {
+                notices.extend(guest.update(game));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        notices
+    }
+    fn host(&self) -> &Host {
+        self.host.as_ref().expect("the host is away")
+    }
+    fn guest(&mut self, guest: usize) -> &mut Guest {
+        &mut self.guests[guest].0
+    }
+    fn game(&mut self, guest: usize) -> &mut CrossesCore {
+        &mut self.guests[guest].1
+    }
+    fn synced(&self, guest: usize) -> bool {
+        made_moves(&self.guests[guest].1) == made_moves(&self.host_game)
+    }
+    fn host_move(&mut self) -> (usize, usize) {
+        play_first_move(&mut self.host_game)
+    }
+}
+
+fn made_moves(game: &CrossesCore) -> &[(usize, usize)] {
+    &game.log[..game.player_manager.current_move()]
+}
+
+#[test]
+fn host_and_guest_play_over_localhost() {
+    let mut table = Table::new();
+    assert_eq!(table.guest(0).seat(), Some(1));
+
+    // The host's own moves reach the guest one by one.
+    while table.host_game.player_manager.current_player() == 0 {
+        table.host_move();
+        table.exchange(|table, _| table.synced(0));
+    }
+
+    // The guest's move is made on the host and comes back.
+    let index = table.host_game.legal_moves()[0].index;
+    table.guest(0).send_move(index).unwrap();
+    table.exchange(|table, _| table.guests[0].1.last_move() == Some(index));
+    assert!(table.synced(0));
+
+    // An illegal move is refused with the host's error.
+    table.guest(0).send_move(index).unwrap();
+    let notices = table.exchange(|_, notices| !notices[0].is_empty());
+    assert!(
+        matches!(notices[0][..], [Notice::Rejected(_)]),
+        "{notices:?}"
+    );
+
+    // Undo isn't a move, so the whole game is sent.
+    table.host_game.back().unwrap();
+    table.exchange(|table, _| table.synced(0));
+}
+
+#[test]
+fn new_games_reach_the_guest() {
+    let mut table = Table::new();
+    let settings = GameSettings {
+        max_x: 8,
+        ..Default::default()
+    };
+    table.host_game = CrossesCore::new(settings.clone()).unwrap();
+    table.exchange(|table, _| table.guests[0].1.settings == settings);
+    assert!(table.game(0).log.is_empty());
+
+    // A rematch starts from an empty log again.
+    table.host_move();
+    table.exchange(|table, _| table.synced(0));
+    table.host_game = CrossesCore::new(settings).unwrap();
+    table.exchange(|table, _| table.guests[0].1.log.is_empty());
+}
+
+#[test]
+fn guest_resyncs_after_reconnecting() {
+    let mut table = Table::new();
+    let port = table.host().port();
+
+    // Closing the host drops the guest.
+    table.host = None;
+    table.exchange(|table, _| !table.guests[0].0.is_connected());
+
+    // Moves made while the guest was away arrive with the game it gets on
+    // reconnecting to a new host on the same port.
+    table.host_move();
+    let host = Host::start(port, vec![1], &table.host_game, Arc::new(|| {})).unwrap();
+    table.host = Some(host);
+    table.exchange(|table, _| table.guests[0].0.is_connected() && table.synced(0));
+    assert_eq!(table.game(0).export(), table.host_game.export());
+}
+
+#[test]
+fn spectators_watch_and_look_back() {
+    let mut table = Table::new();
+    for _ in 0..3 {
+        table.host_move();
+    }
+    table.exchange(|table, _| table.synced(0));
+
+    // A late spectator gets the whole log and doesn't take the free seat.
+    let spectator = table.join(true);
+    assert_eq!(table.guest(spectator).seat(), None);
+    assert_eq!(table.guests[spectator].1.log, table.host_game.log);
+    assert_eq!(table.guest(spectator).live(), Some(3));
+    table.guest(spectator).send_move((0, 0)).unwrap();
+
+    // Looking back keeps the cursor while new moves go to the end of the log.
+    table.game(spectator).seek(1).unwrap();
+    let index = table.host_move();
+    let notices = table.exchange(|table, notices| {
+        table.guests[spectator].0.live() == Some(4) && !notices[spectator].is_empty()
+    });
+    assert!(
+        matches!(notices[spectator][..], [Notice::Rejected(_)]),
+        "{notices:?}"
+    );
+    let game = table.game(spectator);
+    assert_eq!(game.player_manager.current_move(), 1);
+    assert_eq!(game.log.last(), Some(&index));
+    game.seek(4).unwrap();
+    assert_eq!(table.guests[spectator].1.export(), table.host_game.export());
+}
+
+#[test]
+fn the_host_runs_the_clock() {
+    let mut table = Table::new();
+    table.host_game = CrossesCore::new(GameSettings {
+        time_control: Some(TimeControl::default()),
+        ..Default::default()
+    })
+    .unwrap();
+    table.exchange(|table, _| table.guests[0].1.export() == table.host_game.export());
+
+    // The time the host spent comes along with its move and replaces
+    // whatever the guest counted down on its own.
+    table.host_game.tick(Duration::from_secs(10));
+    table.game(0).spend_time(Duration::from_secs(12));
+    table.host_move();
+    table.exchange(|table, _| table.guests[0].1.clock == table.host_game.clock);
+    assert!(table.synced(0));
+
+    // So does a flag, which falls on the host alone.
+    table.host_game.tick(Duration::from_secs(3600));
+    assert!(!table.host_game.flags.is_empty());
+    table.exchange(|table, _| table.guests[0].1.flags == table.host_game.flags);
+    assert!(table.game(0).is_over());
+    assert_eq!(table.game(0).export(), table.host_game.export());
+}